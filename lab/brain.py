@@ -43,6 +43,65 @@ class Brain(object):
         action = io.Action(output.fvel, output.rvel)
         return action
 
+    def probe(self, name):
+        val = lib.sm_probe(self._c, name)
+        samples = [val.ptr[i] for i in range(val.len)]
+        lib.sm_probe_free(val)
+        return samples
+
+    def trace(self):
+        val = lib.sm_trace(self._c)
+        lines = ffi.string(val)
+        lib.sm_trace_free(val)
+        return lines
+
+    # snapshot/restore need a libsm.so built with the `serde` feature
+    def snapshot(self):
+        val = lib.sm_snapshot(self._c)
+        if val == ffi.NULL:
+            return None
+        state = ffi.string(val)
+        lib.sm_snapshot_free(val)
+        return state
+
+    def restore(self, state):
+        return lib.sm_restore(self._c, state)
+
     def run(self, n):
         val = lib.sm_run(self._c, n)
         return [val.ptr[i] for i in range(val.len)]
+
+
+
+def moveToPoint(point):
+    return Brain(lib.sm_move_to_point(point.x, point.y))
+
+
+def rotateTo(theta):
+    return Brain(lib.sm_rotate_to(theta))
+
+
+def followFigure(points):
+    pts = ffi.new("Point_t[]", [(p.x, p.y) for p in points])
+    figure = ffi.new("slice_ref_Point_t *", {'ptr': pts, 'len': len(points)})
+    return Brain(lib.sm_follow_figure(figure[0]))
+
+
+# Dynamic replanner over a grid map of the lower..upper rectangle.
+# lib.sm_replanner prints why it failed on stderr.
+def replanner(goal, lower, upper, cellSize=0.1):
+    point = lambda p: ffi.new("Point_t *", (p.x, p.y))[0]
+    cobj = lib.sm_replanner(point(goal), point(lower), point(upper), cellSize)
+    if cobj == ffi.NULL:
+        raise ValueError("cannot build a replanner, see stderr")
+    return Brain(cobj)
+
+
+# Brain loaded from a netlist file; needs a libsm.so built with the
+# `netlist` feature. lib.sm_from_netlist prints why it failed on stderr.
+class NetlistBrain(Brain):
+    def __init__(self, path):
+        cobj = lib.sm_from_netlist(path)
+        if cobj == ffi.NULL:
+            raise ValueError("cannot build a brain from " + path + ", see stderr")
+        Brain.__init__(self, cobj)
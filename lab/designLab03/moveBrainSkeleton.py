@@ -37,6 +37,8 @@ reload(brain)
 # from brain import Brain, lib
 
 mySM = brain.Brain(brain.lib.sm_simple(0.3))
+# mySM = brain.followFigure(squarePoints)
+# mySM = brain.followFigure(secret)
 
 ######################################################################
 ###
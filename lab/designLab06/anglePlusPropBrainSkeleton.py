@@ -6,7 +6,7 @@ import lib601.util as util
 import lib601.sonarDist as sonarDist
 
 
-from brain import lib, ffi
+from brain import Brain, lib
 
 
 ######################################################################
@@ -35,53 +35,6 @@ class WallFollower(sm.SM):
     def getNextValues(self, state, inp):
         pass
 
-class Brain(object):
-    def __init__(self, cobj):
-        self._c = cobj
-
-    def start(self):
-        lib.sm_reset(self._c)
-
-    def isDone(self):
-        return lib.sm_is_done(self._c)
-
-    def step(self, sensor_input):
-        v = sonarDist.getDistanceRightAndAngle(sensor_input.sonars)
-        print 'getDistanceRightAndAngle ', v
-        if not v[1]:
-           print '******  Angle reading not valid  ******'
-
-        # typedef struct Tuple2_bool_double {
-        #     bool _0;
-
-        #     double _1;
-        # } Tuple2_bool_double_t;
-
-        # typedef struct AnglePropInput {
-        #     double distance;
-
-        #     Tuple2_bool_double_t angle;
-        # } AnglePropInput_t;
-
-        # print inp
-        input = ffi.new("AnglePropInput_t *")
-        input.distance = v[0]
-        if not v[1]:
-            input.angle._0 = False
-            input.angle._1 = 0.0 
-        else:
-            input.angle._0 = True
-            input.angle._1 = v[1] 
-
-        output = lib.sm_step(self._c, input[0])
-        action = io.Action(output.fvel, output.rvel)
-        return action
-
-    def run(self, n):
-        val = lib.sm_run(self._c, n)
-        return [val.ptr[i] for i in range(val.len)]
-
-
 ################
 # Your code here
 ################
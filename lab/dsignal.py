@@ -45,6 +45,10 @@ class Signal:
     """
 
     def __init__(self, cobj):
+        # constructors such as lib.sig return NULL for a degenerate model,
+        # after printing the reason on stderr
+        if cobj == ffi.NULL:
+            raise ValueError("cannot build the signal, see stderr")
         self._c = cobj
     
     __w = None
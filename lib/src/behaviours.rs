@@ -0,0 +1,232 @@
+use crate::{
+    io::{Action, Angle, Point, SensorInput},
+    sm::StateMachine,
+    sm_course::wire,
+};
+
+/// How close to its target the robot must get for a point to count as reached.
+pub const DISTANCE_EPS: f64 = 0.02;
+/// Heading error under which the robot drives instead of turning in place.
+pub const ANGLE_EPS: f64 = 0.05;
+
+const K_FORWARD: f64 = 1.0;
+const K_ROTATE: f64 = 2.0;
+const MAX_FVEL: f64 = 0.5;
+
+/// Proportional controller driving towards the point given with each input:
+/// turns in place until roughly facing it, then drives to it.
+pub struct DynamicMoveToPoint;
+
+impl StateMachine<(Point, SensorInput), Action> for DynamicMoveToPoint {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<(Point, SensorInput)>,
+    ) -> (Self::State, Option<Action>) {
+        (state, input.map(|(goal, sensors)| move_to(goal, &sensors)))
+    }
+}
+
+fn move_to(goal: Point, sensors: &SensorInput) -> Action {
+    let pose = &sensors.odometry;
+    if pose.pos.is_near(goal, DISTANCE_EPS) {
+        return Action::default();
+    }
+    let error = pose.pos.angle_to(goal) - Angle::new(pose.theta);
+    if error.0.abs() > ANGLE_EPS {
+        Action::rotate(K_ROTATE * error.0)
+    } else {
+        Action {
+            fvel: (K_FORWARD * pose.pos.distance_to(goal)).min(MAX_FVEL),
+            rvel: K_ROTATE * error.0,
+        }
+    }
+}
+
+/// Drives to `goal`; done once there.
+pub struct MoveToPoint {
+    pub goal: Point,
+}
+
+impl StateMachine<SensorInput, Action> for MoveToPoint {
+    /// Whether the goal has been reached.
+    type State = bool;
+
+    fn start_state(&self) -> Self::State {
+        false
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        state
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<SensorInput>,
+    ) -> (Self::State, Option<Action>) {
+        match input {
+            Some(sensors) => (
+                sensors.odometry.pos.is_near(self.goal, DISTANCE_EPS),
+                Some(move_to(self.goal, &sensors)),
+            ),
+            None => (state, None),
+        }
+    }
+}
+
+/// Turns in place to `heading`; done once facing it.
+pub struct RotateTo {
+    pub heading: Angle,
+}
+
+impl StateMachine<SensorInput, Action> for RotateTo {
+    /// Whether the heading has been reached.
+    type State = bool;
+
+    fn start_state(&self) -> Self::State {
+        false
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        state
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<SensorInput>,
+    ) -> (Self::State, Option<Action>) {
+        match input {
+            Some(sensors) => {
+                let theta = Angle::new(sensors.odometry.theta);
+                if theta.is_near(self.heading, ANGLE_EPS) {
+                    (true, Some(Action::default()))
+                } else {
+                    (
+                        false,
+                        Some(Action::rotate(K_ROTATE * (self.heading - theta).0)),
+                    )
+                }
+            }
+            None => (state, None),
+        }
+    }
+}
+
+/// Outputs the points of a figure in turn, moving on to the next one once
+/// the robot is near the current one; done after the last one is reached.
+pub struct FollowFigure {
+    pub points: Vec<Point>,
+}
+
+impl StateMachine<SensorInput, Point> for FollowFigure {
+    /// Index of the current target.
+    type State = usize;
+
+    fn start_state(&self) -> Self::State {
+        0
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        state >= self.points.len()
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        *state <= self.points.len()
+    }
+
+    fn next_values(
+        &self,
+        mut state: Self::State,
+        input: Option<SensorInput>,
+    ) -> (Self::State, Option<Point>) {
+        let Some(sensors) = input else {
+            return (state, None);
+        };
+        if let Some(target) = self.points.get(state)
+            && sensors.odometry.pos.is_near(*target, DISTANCE_EPS)
+        {
+            state += 1;
+        }
+        let target = self.points.get(state).or(self.points.last()).copied();
+        (state, target)
+    }
+}
+
+/// Drives through `points` in order, as lib601's `FollowFigure` cascaded
+/// into `DynamicMoveToPoint`.
+pub fn follow_figure(points: Vec<Point>) -> impl StateMachine<SensorInput, Action> {
+    FollowFigure { points }
+        .zip_with(wire(), |target, sensors| (target, sensors))
+        .cascade(DynamicMoveToPoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        behaviours::{DISTANCE_EPS, MoveToPoint, RotateTo, follow_figure},
+        io::{Angle, Point, Pose},
+        sim::Simulator,
+        sm::{StateFullMachine, StateMachine},
+    };
+
+    fn at(x: f64, y: f64, theta: f64) -> Pose {
+        Pose {
+            pos: Point::new(x, y),
+            theta,
+        }
+    }
+
+    #[test]
+    fn test_move_to_point() {
+        let goal = Point::new(-0.5, 0.8);
+        let mut sim = Simulator::new(at(0.0, 0.0, 0.0), vec![]);
+        let mut sm = MoveToPoint { goal }.into_state_full_machine();
+        let trajectory = sim.run(&mut sm, 500);
+        assert!(sm.is_done());
+        assert!(trajectory.len() < 500);
+        assert!(sim.pose().pos.is_near(goal, DISTANCE_EPS));
+    }
+
+    #[test]
+    fn test_rotate_to() {
+        let mut sim = Simulator::new(at(1.0, 1.0, 3.0), vec![]);
+        let mut sm = RotateTo {
+            heading: Angle::new(-PI / 2.0),
+        }
+        .into_state_full_machine();
+        sim.run(&mut sm, 500);
+        assert!(sm.is_done());
+        assert!(Angle::new(sim.pose().theta).is_near(Angle::new(-PI / 2.0), 0.05));
+        assert!(sim.pose().pos.is_near(Point::new(1.0, 1.0), 1e-9));
+    }
+
+    #[test]
+    fn test_follow_figure() {
+        let square = vec![
+            Point::new(0.5, 0.5),
+            Point::new(0.0, 1.0),
+            Point::new(-0.5, 0.5),
+            Point::new(0.0, 0.0),
+        ];
+        let mut sim = Simulator::new(at(0.0, 0.0, 0.0), vec![]);
+        let mut sm = follow_figure(square.clone()).into_state_full_machine();
+        let trajectory = sim.run(&mut sm, 2000);
+        assert!(sm.is_done());
+        let mut visited = square.iter();
+        let mut target = visited.next();
+        for pose in &trajectory {
+            if target.is_some_and(|t| pose.pos.is_near(*t, DISTANCE_EPS)) {
+                target = visited.next();
+            }
+        }
+        assert!(target.is_none(), "corners visited out of order");
+    }
+}
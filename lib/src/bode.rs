@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+use crate::complex::Complex;
+
+/// A system function sampled on the unit circle, as for a Bode plot. The
+/// margins are those of a loop closed around it, so sample the open-loop
+/// function.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyResponse {
+    /// Angular frequencies, in radians per step.
+    pub omega: Vec<f64>,
+    pub magnitude_db: Vec<f64>,
+    /// Phase in radians, unwrapped so it moves by at most π between
+    /// samples.
+    pub phase: Vec<f64>,
+}
+
+impl FrequencyResponse {
+    /// From `(omega, response)` samples in increasing `omega`.
+    pub fn new(samples: impl IntoIterator<Item = (f64, Complex)>) -> Self {
+        let mut response = Self::default();
+        for (omega, h) in samples {
+            let mut phase = h.arg();
+            if let Some(last) = response.phase.last() {
+                phase += 2.0 * PI * ((last - phase) / (2.0 * PI)).round();
+            }
+            response.omega.push(omega);
+            response.magnitude_db.push(20.0 * h.norm().log10());
+            response.phase.push(phase);
+        }
+        response
+    }
+
+    /// How many dB the gain can grow before the loop goes unstable: minus
+    /// the magnitude where the phase first reaches -π.
+    pub fn gain_margin(&self) -> Option<f64> {
+        let at = self.crossing(&self.phase, -PI)?;
+        Some(-interpolate(&self.magnitude_db, at))
+    }
+
+    /// How much more phase lag, in radians, the loop tolerates: the phase
+    /// plus π where the magnitude first reaches 0 dB.
+    pub fn phase_margin(&self) -> Option<f64> {
+        let at = self.crossing(&self.magnitude_db, 0.0)?;
+        Some(interpolate(&self.phase, at) + PI)
+    }
+
+    /// The first frequency where the magnitude is 3 dB below the one at the
+    /// lowest frequency sampled.
+    pub fn bandwidth(&self) -> Option<f64> {
+        let level = self.magnitude_db.first()? - 10.0 * 2f64.log10();
+        let at = self.crossing(&self.magnitude_db, level)?;
+        Some(interpolate(&self.omega, at))
+    }
+
+    /// Where `values` first reaches `level`, as a sample index and the
+    /// fraction of the way to the next sample.
+    fn crossing(&self, values: &[f64], level: f64) -> Option<(usize, f64)> {
+        values.windows(2).enumerate().find_map(|(k, pair)| {
+            let (v0, v1) = (pair[0] - level, pair[1] - level);
+            if v0 == 0.0 {
+                Some((k, 0.0))
+            } else if v0 * v1 <= 0.0 {
+                Some((k, v0 / (v0 - v1)))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn interpolate(values: &[f64], (k, t): (usize, f64)) -> f64 {
+    if t == 0.0 {
+        values[k]
+    } else {
+        values[k] + t * (values[k + 1] - values[k])
+    }
+}
+
+/// `n` frequencies spread evenly on a log scale from `from` to `to`.
+pub fn log_space(from: f64, to: f64, n: usize) -> Vec<f64> {
+    let step = (to / from).ln() / (n.max(2) - 1) as f64;
+    (0..n).map(|k| from * (step * k as f64).exp()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::log_space;
+    use crate::{
+        poly::Poly,
+        sf::{SystemFunction, delay, gain},
+    };
+
+    fn assert_near(expected: f64, actual: Option<f64>) {
+        let actual = actual.unwrap();
+        assert!((expected - actual).abs() < 1e-3, "{expected} != {actual}");
+    }
+
+    #[test]
+    fn test_lowpass() {
+        // 0.5 / (1 - 0.5R): 0 dB at DC, 1/3 at ω = π
+        let lowpass = SystemFunction::new(Poly::new([0.5]), Poly::new([-0.5, 1.0]));
+        let response = lowpass.frequency_response(log_space(1e-3, PI, 2000));
+        assert!(response.magnitude_db[0].abs() < 1e-3);
+        assert!((response.magnitude_db[1999] - 20.0 * (1.0 / 3.0f64).log10()).abs() < 1e-9);
+        // |H|² = 0.25 / (1.25 - cos ω) halves where cos ω = 0.75
+        assert_near(0.75f64.acos(), response.bandwidth());
+        assert_eq!(response.gain_margin(), None);
+    }
+
+    #[test]
+    fn test_margins() {
+        // R / (1 - R): |H| = 1 / (2 sin(ω/2)), phase -(π + ω) / 2
+        let accumulator = gain(1.0).cascade(delay()).feedback_add(Some(gain(1.0)));
+        let response = accumulator.frequency_response(log_space(1e-3, PI, 4000));
+        assert_near(-(PI + 1e-3) / 2.0, response.phase.first().copied());
+        assert_near(PI / 3.0, response.phase_margin());
+        assert_near(20.0 * 2f64.log10(), response.gain_margin());
+
+        // the phase of R^3 keeps falling past -π instead of wrapping
+        let delays = gain(0.5).cascade(delay()).cascade(delay()).cascade(delay());
+        let response = delays.frequency_response(log_space(1e-3, PI, 2000));
+        assert_near(-3.0 * PI, response.phase.last().copied());
+        assert_near(20.0 * 2f64.log10(), response.gain_margin());
+        assert_eq!(response.phase_margin(), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::{any::Any, fmt::Debug};
+
+use crate::sm::StateMachine;
+
+trait AnyState: Any {
+    fn clone_box(&self) -> Box<dyn AnyState>;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Clone> AnyState for T {
+    fn clone_box(&self) -> Box<dyn AnyState> {
+        Box::new(self.clone())
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Type-erased state of a `BoxedStateMachine`.
+pub struct DynState(Box<dyn AnyState>);
+
+impl DynState {
+    pub fn new<T: Any + Clone>(state: T) -> Self {
+        Self(Box::new(state))
+    }
+
+    pub fn downcast<T: Any>(self) -> Option<T> {
+        self.0.into_any().downcast().ok().map(|state| *state)
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl Clone for DynState {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl Debug for DynState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DynState(..)")
+    }
+}
+
+/// Object-safe counterpart of `StateMachine`, implemented for every machine
+/// whose state is `'static`.
+pub trait DynStateMachine<In, Out> {
+    fn dyn_start_state(&self) -> DynState;
+    fn dyn_next_values(&self, state: DynState, input: Option<In>) -> (DynState, Option<Out>);
+    fn dyn_done(&self, state: DynState) -> bool;
+    fn dyn_validate(&self, state: &DynState) -> bool;
+}
+
+const FOREIGN_STATE: &str = "state does not belong to this machine";
+
+impl<In, Out, SM> DynStateMachine<In, Out> for SM
+where
+    SM: StateMachine<In, Out>,
+    SM::State: 'static,
+{
+    fn dyn_start_state(&self) -> DynState {
+        DynState::new(self.start_state())
+    }
+
+    fn dyn_next_values(&self, state: DynState, input: Option<In>) -> (DynState, Option<Out>) {
+        let state = state.downcast().expect(FOREIGN_STATE);
+        let (new_state, output) = self.next_values(state, input);
+        (DynState::new(new_state), output)
+    }
+
+    fn dyn_done(&self, state: DynState) -> bool {
+        self.done(state.downcast().expect(FOREIGN_STATE))
+    }
+
+    fn dyn_validate(&self, state: &DynState) -> bool {
+        state
+            .downcast_ref()
+            .is_some_and(|state| self.validate(state))
+    }
+}
+
+/// A machine whose concrete type, state included, is only known at runtime.
+/// It is itself a `StateMachine`, so it composes with every combinator.
+pub struct BoxedStateMachine<In, Out>(Box<dyn DynStateMachine<In, Out>>);
+
+impl<In, Out> BoxedStateMachine<In, Out> {
+    pub fn new<SM>(machine: SM) -> Self
+    where
+        SM: StateMachine<In, Out> + 'static,
+        SM::State: 'static,
+    {
+        Self(Box::new(machine))
+    }
+}
+
+impl<In, Out> StateMachine<In, Out> for BoxedStateMachine<In, Out> {
+    type State = DynState;
+
+    fn start_state(&self) -> Self::State {
+        self.0.dyn_start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.0.dyn_done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.0.dyn_validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        self.0.dyn_next_values(state, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dyn_sm::{BoxedStateMachine, DynState},
+        sm::StateMachine,
+        sm_course::{delay, scale, wire},
+    };
+    use std::ops::Add;
+
+    #[test]
+    fn test_runtime_cascade() {
+        let gains = [2, 3, 4];
+        let mut sm: BoxedStateMachine<i32, i32> = wire().boxed();
+        for k in gains {
+            sm = sm.cascade(scale(k)).boxed();
+        }
+        sm = sm.cascade(delay(0)).boxed();
+        assert_eq!(
+            &[0, 24, 48, 72],
+            sm.transduce([1, 2, 3, 4]).collect::<Vec<_>>().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_combinators_over_boxed() {
+        let machines: Vec<BoxedStateMachine<i32, i32>> =
+            vec![delay(1).boxed(), (|i: i32| i * 10).boxed()];
+        let mut machines = machines.into_iter();
+        let first = machines.next().unwrap();
+        let second = machines.next().unwrap();
+        assert_eq!(
+            &[(1, 10), (1, 20), (2, 30)],
+            first
+                .parallel(second)
+                .transduce([1, 2, 3])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+
+        let acc = wire().boxed().feedback_op(delay(0).boxed(), i32::add);
+        assert_eq!(
+            &[1, 3, 6],
+            acc.transduce([1, 2, 3]).collect::<Vec<_>>().as_slice()
+        );
+
+        let tsm = |c: char| (move |_state, _input: i32| (true, c), |state| state, false).boxed();
+        assert_eq!(
+            &['a', 'b', 'a', 'b'],
+            tsm('a')
+                .seq(tsm('b'))
+                .boxed()
+                .repeat(Some(2))
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            &[0, 3, 4, 9],
+            (|i: i32| i * 2)
+                .boxed()
+                .switch((|i| i * 3).boxed(), |i| i % 2 == 0)
+                .transduce(0..4)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_dyn_state() {
+        let state = DynState::new((1, 'a')).clone();
+        assert_eq!(state.downcast_ref::<(i32, char)>(), Some(&(1, 'a')));
+        assert!(state.downcast_ref::<i32>().is_none());
+        assert_eq!(state.downcast::<(i32, char)>(), Some((1, 'a')));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{
+    io::{Point, SensorInput},
+    robot::RobotModel,
+    sm::StateMachine,
+    sonars::{WorldHit, world_hits},
+    world::World,
+};
+
+/// Log-odds updates applied to the cells a sonar beam touches.
+#[derive(Debug, Clone, Copy)]
+pub struct BeamModel {
+    /// Added to the cell the beam ended in, when it hit something.
+    pub hit: f64,
+    /// Added to every cell the beam crossed freely; negative.
+    pub miss: f64,
+    /// Log-odds are clamped to `[-bound, bound]` so cells can change again.
+    pub bound: f64,
+}
+
+impl Default for BeamModel {
+    fn default() -> Self {
+        Self {
+            hit: 0.9,
+            miss: -0.4,
+            bound: 4.0,
+        }
+    }
+}
+
+/// Occupancy grid over a rectangle of the world, each cell holding the
+/// log-odds of being occupied; unseen cells are at 0.
+#[derive(Debug, Clone)]
+pub struct GridMap {
+    /// World position of the lower-left corner of cell `(0, 0)`.
+    pub origin: Point,
+    /// Side of a cell.
+    pub resolution: f64,
+    pub width: usize,
+    pub height: usize,
+    log_odds: Vec<f64>,
+}
+
+/// Column and row of a grid cell.
+pub type Cell = (usize, usize);
+
+impl GridMap {
+    /// A grid covering `size` from `origin`, with cells of side `resolution`.
+    pub fn new(origin: Point, size: Point, resolution: f64) -> Self {
+        let width = (size.x / resolution).ceil() as usize;
+        let height = (size.y / resolution).ceil() as usize;
+        Self {
+            origin,
+            resolution,
+            width,
+            height,
+            log_odds: vec![0.0; width * height],
+        }
+    }
+
+    /// A grid covering the world's arena.
+    pub fn from_world(world: &World, resolution: f64) -> Self {
+        Self::new(Point::new(0.0, 0.0), world.bounds, resolution)
+    }
+
+    /// The cell containing `p`, if it is on the grid.
+    pub fn cell(&self, p: Point) -> Option<Cell> {
+        let (x, y) = self.grid_coords(p);
+        self.in_bounds(x.floor() as i64, y.floor() as i64)
+    }
+
+    pub fn cell_center(&self, (i, j): Cell) -> Point {
+        Point::new(
+            self.origin.x + (i as f64 + 0.5) * self.resolution,
+            self.origin.y + (j as f64 + 0.5) * self.resolution,
+        )
+    }
+
+    /// Log odds that the cell is occupied. Like `probability` and
+    /// `is_occupied`, expects a cell on the grid, such as one from `cell`:
+    /// others may alias a cell of the next row.
+    pub fn log_odds(&self, (i, j): Cell) -> f64 {
+        debug_assert!(
+            i < self.width && j < self.height,
+            "({i}, {j}) is off the grid"
+        );
+        self.log_odds[j * self.width + i]
+    }
+
+    /// Probability that the cell is occupied.
+    pub fn probability(&self, cell: Cell) -> f64 {
+        1.0 - 1.0 / (1.0 + self.log_odds(cell).exp())
+    }
+
+    /// Whether the cell is more likely occupied than free.
+    pub fn is_occupied(&self, cell: Cell) -> bool {
+        self.log_odds(cell) > 0.0
+    }
+
+    /// Whether no occupied cell has its center within `radius` of the
+    /// center of `cell`.
+    pub fn is_clear(&self, (i, j): Cell, radius: f64) -> bool {
+        let reach = (radius / self.resolution).floor() as i64;
+        let center = self.cell_center((i, j));
+        (-reach..=reach)
+            .flat_map(|di| (-reach..=reach).map(move |dj| (i as i64 + di, j as i64 + dj)))
+            .filter_map(|(i, j)| self.in_bounds(i, j))
+            .filter(|&cell| self.cell_center(cell).distance_to(center) <= radius)
+            .all(|cell| !self.is_occupied(cell))
+    }
+
+    /// Clears the cells `hit` crossed and, if it hit something, marks the
+    /// cell it ended in.
+    pub fn update(&mut self, beam: &BeamModel, hit: &WorldHit) {
+        let cells = self.ray_cells(hit.origin, hit.point);
+        let Some((&last, crossed)) = cells.split_last() else {
+            return;
+        };
+        for &cell in crossed {
+            self.add(beam, cell, beam.miss);
+        }
+        let end = self.cell(hit.point);
+        match (end == Some(last), hit.in_range) {
+            (true, true) => self.add(beam, last, beam.hit),
+            _ => self.add(beam, last, beam.miss),
+        }
+    }
+
+    fn add(&mut self, beam: &BeamModel, (i, j): Cell, delta: f64) {
+        let l = &mut self.log_odds[j * self.width + i];
+        *l = (*l + delta).clamp(-beam.bound, beam.bound);
+    }
+
+    fn grid_coords(&self, p: Point) -> (f64, f64) {
+        (
+            (p.x - self.origin.x) / self.resolution,
+            (p.y - self.origin.y) / self.resolution,
+        )
+    }
+
+    fn in_bounds(&self, i: i64, j: i64) -> Option<Cell> {
+        (0 <= i && (i as usize) < self.width && 0 <= j && (j as usize) < self.height)
+            .then_some((i as usize, j as usize))
+    }
+
+    /// The on-grid cells the segment from `from` to `to` passes through, in
+    /// order, walking cell boundaries as they are crossed.
+    pub fn ray_cells(&self, from: Point, to: Point) -> Vec<Cell> {
+        let (fx, fy) = self.grid_coords(from);
+        let (tx, ty) = self.grid_coords(to);
+        let (mut i, mut j) = (fx.floor() as i64, fy.floor() as i64);
+        let (ei, ej) = (tx.floor() as i64, ty.floor() as i64);
+        let (dx, dy) = (tx - fx, ty - fy);
+        let axis = |f: f64, d: f64| {
+            if d > 0.0 {
+                (1, (f.floor() + 1.0 - f) / d, 1.0 / d)
+            } else if d < 0.0 {
+                (-1, (f - f.floor()) / -d, -1.0 / d)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_i, mut t_i, delta_i) = axis(fx, dx);
+        let (step_j, mut t_j, delta_j) = axis(fy, dy);
+
+        let mut cells = Vec::new();
+        for _ in 0..=(ei - i).abs() + (ej - j).abs() {
+            cells.extend(self.in_bounds(i, j));
+            if (i, j) == (ei, ej) {
+                break;
+            }
+            if t_i < t_j {
+                t_i += delta_i;
+                i += step_i;
+            } else {
+                t_j += delta_j;
+                j += step_j;
+            }
+        }
+        cells
+    }
+}
+
+/// Builds a map from the sonar readings and odometry it is fed, outputting
+/// the map after each update.
+pub struct MapMaker {
+    pub start: GridMap,
+    pub robot: RobotModel,
+    pub beam: BeamModel,
+}
+
+impl MapMaker {
+    /// Maps onto `start` with the Pioneer and the default beam model.
+    pub fn new(start: GridMap) -> Self {
+        Self {
+            start,
+            robot: RobotModel::default(),
+            beam: BeamModel::default(),
+        }
+    }
+}
+
+impl StateMachine<SensorInput, GridMap> for MapMaker {
+    type State = GridMap;
+
+    fn start_state(&self) -> Self::State {
+        self.start.clone()
+    }
+
+    fn next_values(
+        &self,
+        mut state: Self::State,
+        input: Option<SensorInput>,
+    ) -> (Self::State, Option<GridMap>) {
+        let Some(input) = input else {
+            return (state, None);
+        };
+        for hit in world_hits(&self.robot, &input.odometry, &input.sonars) {
+            state.update(&self.beam, &hit);
+        }
+        (state.clone(), Some(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        io::Pose,
+        sim::{Simulator, Wall},
+    };
+
+    fn grid() -> GridMap {
+        GridMap::new(Point::new(0.0, 0.0), Point::new(4.0, 4.0), 0.1)
+    }
+
+    #[test]
+    fn test_cells() {
+        let map = grid();
+        assert_eq!((map.width, map.height), (40, 40));
+        assert_eq!(map.cell(Point::new(0.05, 3.99)), Some((0, 39)));
+        assert_eq!(map.cell(Point::new(-0.01, 1.0)), None);
+        assert!(
+            map.cell_center((3, 2))
+                .is_near(Point::new(0.35, 0.25), 1e-12)
+        );
+        assert_eq!(map.probability((3, 2)), 0.5);
+    }
+
+    #[test]
+    fn test_ray_cells() {
+        let map = grid();
+        let straight = map.ray_cells(Point::new(0.05, 0.15), Point::new(0.45, 0.15));
+        assert_eq!(straight, vec![(0, 1), (1, 1), (2, 1), (3, 1), (4, 1)]);
+        let diagonal = map.ray_cells(Point::new(0.05, 0.02), Point::new(0.32, 0.25));
+        assert_eq!(diagonal.first(), Some(&(0, 0)));
+        assert_eq!(diagonal.last(), Some(&(3, 2)));
+        for pair in diagonal.windows(2) {
+            let ((i0, j0), (i1, j1)) = (pair[0], pair[1]);
+            assert_eq!(i0.abs_diff(i1) + j0.abs_diff(j1), 1, "{diagonal:?}");
+        }
+        let leaving = map.ray_cells(Point::new(0.15, 0.15), Point::new(-1.0, 0.15));
+        assert_eq!(leaving, vec![(1, 1), (0, 1)]);
+    }
+
+    #[test]
+    fn test_clearance() {
+        let mut map = grid();
+        let beam = BeamModel::default();
+        map.update(
+            &beam,
+            &WorldHit {
+                origin: Point::new(1.05, 1.05),
+                point: Point::new(1.55, 1.05),
+                in_range: true,
+            },
+        );
+        assert!(!map.is_clear((15, 10), 0.0));
+        assert!(!map.is_clear((13, 10), 0.2));
+        assert!(!map.is_clear((14, 11), 0.2));
+        assert!(map.is_clear((13, 11), 0.2));
+        assert!(map.is_clear((12, 10), 0.2));
+    }
+
+    #[test]
+    fn test_beam_update() {
+        let mut map = grid();
+        let beam = BeamModel::default();
+        let hit = WorldHit {
+            origin: Point::new(1.05, 1.05),
+            point: Point::new(1.55, 1.05),
+            in_range: true,
+        };
+        map.update(&beam, &hit);
+        assert!(map.is_occupied((15, 10)));
+        assert!((10..15).all(|i| map.log_odds((i, 10)) == beam.miss));
+        let miss = WorldHit {
+            in_range: false,
+            ..hit
+        };
+        for _ in 0..20 {
+            map.update(&beam, &miss);
+        }
+        assert!(!map.is_occupied((15, 10)));
+        assert_eq!(map.log_odds((12, 10)), -beam.bound);
+    }
+
+    #[test]
+    fn test_map_maker() {
+        // walls through the middle of column 30 and row 10
+        let walls = vec![
+            Wall::new(Point::new(3.05, 0.0), Point::new(3.05, 4.0)),
+            Wall::new(Point::new(0.0, 1.05), Point::new(4.0, 1.05)),
+        ];
+        let inputs = [0.0, 0.5, -0.5, -1.0].map(|theta| {
+            let pose = Pose {
+                pos: Point::new(2.0, 2.0),
+                theta,
+            };
+            Simulator::new(pose, walls.clone()).sensor_input()
+        });
+        let map = MapMaker::new(grid()).transduce(inputs).last().unwrap();
+        let occupied = (0..map.width)
+            .flat_map(|i| (0..map.height).map(move |j| (i, j)))
+            .filter(|&cell| map.is_occupied(cell))
+            .collect::<Vec<_>>();
+        assert!(
+            occupied.iter().all(|&(i, j)| i == 30 || j == 10),
+            "{occupied:?}"
+        );
+        assert!(occupied.iter().filter(|&&(i, _)| i == 30).count() >= 2);
+        assert!(occupied.iter().filter(|&&(_, j)| j == 10).count() >= 2);
+        let at = |x, y| map.cell(Point::new(x, y)).unwrap();
+        assert!(map.log_odds(at(2.5, 2.0)) < 0.0);
+        assert!(map.log_odds(at(2.0, 1.5)) < 0.0);
+        assert_eq!(map.log_odds(at(0.5, 3.5)), 0.0);
+    }
+}
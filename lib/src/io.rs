@@ -74,6 +74,35 @@ pub struct Pose {
     pub theta: f64,
 }
 
+impl Pose {
+    /// Maps `p` from this pose's frame into the frame the pose is given in.
+    pub fn transform_point(&self, p: Point) -> Point {
+        let (sin, cos) = self.theta.sin_cos();
+        self.pos + Point::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos)
+    }
+
+    /// `other`, given in this pose's frame, expressed in the frame this pose
+    /// is given in.
+    pub fn compose(&self, other: &Pose) -> Pose {
+        Pose {
+            pos: self.transform_point(other.pos),
+            theta: Angle::new(self.theta + other.theta).0,
+        }
+    }
+
+    /// The pose that undoes this one: `p.compose(&p.inverse())` is the
+    /// identity, and `p.inverse().transform_point` maps points back into
+    /// this pose's frame.
+    pub fn inverse(&self) -> Pose {
+        let (sin, cos) = self.theta.sin_cos();
+        let Point { x, y } = self.pos;
+        Pose {
+            pos: Point::new(-x * cos - y * sin, x * sin - y * cos),
+            theta: Angle::new(-self.theta).0,
+        }
+    }
+}
+
 #[derive_ReprC]
 #[repr(C)]
 #[derive(Debug, Clone, Default)]
@@ -128,3 +157,47 @@ impl Sub<Angle> for f64 {
         self - rhs.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-12;
+
+    fn pose(x: f64, y: f64, theta: f64) -> Pose {
+        Pose {
+            pos: Point::new(x, y),
+            theta,
+        }
+    }
+
+    fn assert_pose_near(p: &Pose, q: &Pose) {
+        assert!(p.pos.is_near(q.pos, EPSILON), "{p:?} != {q:?}");
+        assert!(Angle::new(p.theta).is_near(Angle::new(q.theta), EPSILON));
+    }
+
+    #[test]
+    fn test_transform_point() {
+        let robot = pose(1.0, 2.0, PI / 2.0);
+        let p = robot.transform_point(Point::new(0.5, 0.0));
+        assert!(p.is_near(Point::new(1.0, 2.5), EPSILON), "{p:?}");
+        let back = robot.inverse().transform_point(p);
+        assert!(back.is_near(Point::new(0.5, 0.0), EPSILON), "{back:?}");
+    }
+
+    #[test]
+    fn test_compose_and_inverse() {
+        let a = pose(1.0, -2.0, 0.7);
+        let b = pose(-0.3, 0.4, 3.0);
+        assert_pose_near(&a.compose(&a.inverse()), &Pose::default());
+        assert_pose_near(&a.inverse().compose(&a), &Pose::default());
+        assert_pose_near(&a.compose(&b).inverse(), &b.inverse().compose(&a.inverse()));
+        let p = Point::new(0.2, 0.9);
+        assert!(
+            a.compose(&b)
+                .transform_point(p)
+                .is_near(a.transform_point(b.transform_point(p)), EPSILON)
+        );
+        assert!(a.compose(&b).theta <= PI);
+    }
+}
@@ -1,24 +1,44 @@
 use crate::{
-    io::{Action, Angle, SensorInput},
-    sf::SystemFunction,
+    behaviours::{MoveToPoint, RotateTo, follow_figure},
+    grid_map::GridMap,
+    io::{Action, Angle, Point, SensorInput},
+    replanner::replanner,
+    sf::{SfError, SystemFunction},
     sig::{Signal, constant},
     sm::{StateFullMachine, StateMachine},
     sm_course::delay,
-    sonars::get_distance_right,
+    sonars::get_distance_right_and_angle,
+    trace::Tracer,
 };
-use safer_ffi::{option::TaggedOption, prelude::*};
+use safer_ffi::prelude::*;
 use std::cell::Cell;
+pub mod behaviours;
+pub mod bode;
+pub mod complex;
+pub mod dyn_sm;
+pub mod grid_map;
 pub mod io;
+#[cfg(feature = "netlist")]
+pub mod netlist;
 
 pub mod poly;
 
 pub mod sf;
 
 pub mod opt;
+pub mod replanner;
+pub mod robot;
+pub mod search;
 pub mod sig;
+pub mod sim;
 pub mod sm;
 pub mod sm_course;
+#[cfg(feature = "serde")]
+pub mod snapshot;
 pub mod sonars;
+pub mod trace;
+pub mod undef;
+pub mod world;
 
 #[derive_ReprC]
 #[repr(opaque)]
@@ -28,29 +48,27 @@ where
     O: ReprC,
 {
     sfm: Box<dyn StateFullMachine<I, O>>,
+    tracer: Tracer,
 }
 
 const V: f64 = 0.1;
-const T: f64 = 0.1;
+/// Sample period of the robot, in seconds.
+pub const T: f64 = 0.1;
 
-fn controller(desired_d: f64, k3: f64, k4: f64) -> impl StateMachine<(f64, Option<f64>), Action> {
+fn controller(
+    desired_d: f64,
+    k3: f64,
+    k4: f64,
+) -> impl StateMachine<(f64, Option<f64>), Action, State = ()> {
     (move |(ds, angle): (f64, Option<f64>)| angle.map(|angle| k3 * (desired_d - ds) - k4 * angle))
-        .cascade(|rvel: Option<f64>| Action {
+        .map_output(|rvel: Option<f64>| Action {
             fvel: V,
             rvel: rvel.unwrap_or(0.0),
         })
 }
 
-fn sensor() -> impl StateMachine<AnglePropInput, (f64, Option<f64>)> {
-    |sonars: AnglePropInput| (sonars.distance, sonars.angle.into_rust())
-}
-
-#[derive_ReprC]
-#[repr(C)]
-#[derive(Debug, Clone)]
-pub struct AnglePropInput {
-    distance: f64,
-    angle: TaggedOption<f64>,
+fn sensor() -> impl StateMachine<SensorInput, (f64, Option<f64>), State = ()> {
+    |input: SensorInput| get_distance_right_and_angle(&input.sonars)
 }
 
 #[ffi_export]
@@ -58,36 +76,173 @@ fn sm(
     desired_d: f64,
     k1: f64,
     k2: f64,
-) -> repr_c::Box<StateFullMachineOpaque<AnglePropInput, Action>> {
+) -> repr_c::Box<StateFullMachineOpaque<SensorInput, Action>> {
     dbg!((desired_d, (k1, k2)));
-    Box::new(StateFullMachineOpaque {
-        sfm: Box::new(
-            sensor()
-                .cascade(controller(desired_d, k1, k2))
-                .into_state_full_machine(),
-        ),
-    })
-    .into()
+    let tracer = Tracer::new();
+    let brain = sensor()
+        .traced("sensor", &tracer)
+        .probe("rightDistance", &tracer, |(d, _)| *d)
+        .cascade(controller(desired_d, k1, k2).traced("controller", &tracer));
+    #[cfg(feature = "serde")]
+    let sfm = Box::new(brain.into_snapshot_machine());
+    #[cfg(not(feature = "serde"))]
+    let sfm = Box::new(brain.into_state_full_machine());
+    Box::new(StateFullMachineOpaque { sfm, tracer }).into()
 }
 
 #[ffi_export]
-fn sm_step(
-    sm: &'_ mut StateFullMachineOpaque<AnglePropInput, Action>,
-    input: AnglePropInput,
-) -> Action {
+fn sm_step(sm: &'_ mut StateFullMachineOpaque<SensorInput, Action>, input: SensorInput) -> Action {
     sm.sfm.step(Some(input)).unwrap_or_default()
 }
 
 #[ffi_export]
-fn sm_is_done(sm: &'_ mut StateFullMachineOpaque<AnglePropInput, Action>) -> bool {
+fn sm_is_done(sm: &'_ mut StateFullMachineOpaque<SensorInput, Action>) -> bool {
     sm.sfm.is_done()
 }
 
 #[ffi_export]
-fn sm_reset(sm: &'_ mut StateFullMachineOpaque<AnglePropInput, Action>) {
+fn sm_reset(sm: &'_ mut StateFullMachineOpaque<SensorInput, Action>) {
+    sm.tracer.clear();
     sm.sfm.reset()
 }
 
+/// Samples recorded so far by the probe `name`, one per step and NaN for
+/// the steps without an output; release with `sm_probe_free`.
+#[ffi_export]
+fn sm_probe(
+    sm: &'_ StateFullMachineOpaque<SensorInput, Action>,
+    name: char_p::Ref<'_>,
+) -> repr_c::Vec<f64> {
+    sm.tracer.probe(name.to_str()).into()
+}
+
+#[ffi_export]
+fn sm_probe_free(samples: repr_c::Vec<f64>) {
+    drop(samples)
+}
+
+/// One line per recorded step; release with `sm_trace_free`.
+#[ffi_export]
+fn sm_trace(sm: &'_ StateFullMachineOpaque<SensorInput, Action>) -> char_p::Box {
+    let lines = sm
+        .tracer
+        .records()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    char_p::new(lines.join("\n"))
+}
+
+#[ffi_export]
+fn sm_trace_free(trace: char_p::Box) {
+    drop(trace)
+}
+
+/// The machine state as JSON, or null if it cannot be serialized; release
+/// with `sm_snapshot_free`. Only the `sm` brain supports snapshots: the
+/// others always return null.
+#[cfg(feature = "serde")]
+#[ffi_export]
+fn sm_snapshot(sm: &'_ StateFullMachineOpaque<SensorInput, Action>) -> Option<char_p::Box> {
+    sm.sfm.snapshot().ok().map(char_p::new)
+}
+
+#[cfg(feature = "serde")]
+#[ffi_export]
+fn sm_snapshot_free(snapshot: char_p::Box) {
+    drop(snapshot)
+}
+
+/// Restores a state produced by `sm_snapshot`; returns false and leaves the
+/// machine untouched if `snapshot` does not parse, if the machine cannot
+/// step from it, or if the machine is not an `sm` brain.
+#[cfg(feature = "serde")]
+#[ffi_export]
+fn sm_restore(
+    sm: &'_ mut StateFullMachineOpaque<SensorInput, Action>,
+    snapshot: char_p::Ref<'_>,
+) -> bool {
+    sm.sfm.restore(snapshot.to_str()).is_ok()
+}
+
+fn into_brain<SM>(sm: SM) -> repr_c::Box<StateFullMachineOpaque<SensorInput, Action>>
+where
+    SM: StateMachine<SensorInput, Action> + 'static,
+{
+    Box::new(StateFullMachineOpaque {
+        sfm: Box::new(sm.into_state_full_machine()),
+        tracer: Tracer::new(),
+    })
+    .into()
+}
+
+#[ffi_export]
+fn sm_move_to_point(x: f64, y: f64) -> repr_c::Box<StateFullMachineOpaque<SensorInput, Action>> {
+    into_brain(MoveToPoint {
+        goal: Point::new(x, y),
+    })
+}
+
+#[ffi_export]
+fn sm_rotate_to(theta: f64) -> repr_c::Box<StateFullMachineOpaque<SensorInput, Action>> {
+    into_brain(RotateTo {
+        heading: Angle::new(theta),
+    })
+}
+
+#[ffi_export]
+fn sm_follow_figure(
+    points: c_slice::Ref<'_, Point>,
+) -> repr_c::Box<StateFullMachineOpaque<SensorInput, Action>> {
+    into_brain(follow_figure(points.as_slice().to_vec()))
+}
+
+/// Drives to `goal`, mapping the `lower` to `upper` rectangle in cells of
+/// side `cell_size` and replanning around what the sonars find; prints the
+/// error and returns null if the rectangle or `cell_size` is empty or not
+/// finite.
+#[ffi_export]
+fn sm_replanner(
+    goal: Point,
+    lower: Point,
+    upper: Point,
+    cell_size: f64,
+) -> Option<repr_c::Box<StateFullMachineOpaque<SensorInput, Action>>> {
+    let size = upper - lower;
+    let positive = |x: f64| x.is_finite() && x > 0.0;
+    if !positive(cell_size) || !positive(size.x) || !positive(size.y) {
+        eprintln!("sm_replanner: cannot map {lower:?} to {upper:?} in cells of {cell_size}");
+        return None;
+    }
+    Some(into_brain(replanner(
+        goal,
+        GridMap::new(lower, size, cell_size),
+    )))
+}
+
+/// Loads the brain described by the netlist file at `path`; prints the
+/// error and returns null if it cannot be built.
+#[cfg(feature = "netlist")]
+#[ffi_export]
+fn sm_from_netlist(
+    path: char_p::Ref<'_>,
+) -> Option<repr_c::Box<StateFullMachineOpaque<SensorInput, Action>>> {
+    let brain = std::fs::read_to_string(path.to_str())
+        .map_err(|err| err.to_string())
+        .and_then(|src| {
+            netlist::Netlist::parse(&src)
+                .and_then(|netlist| netlist.brain())
+                .map_err(|err| err.to_string())
+        });
+    match brain {
+        Ok(brain) => Some(into_brain(brain)),
+        Err(err) => {
+            eprintln!("{}: {err}", path.to_str());
+            None
+        }
+    }
+}
+
 #[derive_ReprC]
 #[repr(opaque)]
 pub struct SignalOpaque<O>
@@ -114,48 +269,66 @@ fn sig_cos(omega: f64, theta: f64) -> repr_c::Box<SignalOpaque<f64>> {
 }
 
 pub fn delay_plus_prop_model(k1: f64, k2: f64) -> SystemFunction {
-    let controller = sf::gain(k1).feedforward_add(Some(sf::gain(k2).cascade(sf::delay())));
+    let sf = try_delay_plus_prop_model(k1, k2).unwrap_or_else(|err| panic!("{err}"));
+    println!("{sf}");
+    sf
+}
+
+pub fn try_delay_plus_prop_model(k1: f64, k2: f64) -> Result<SystemFunction, SfError> {
+    let controller =
+        sf::try_gain(k1)?.try_feedforward_add(Some(sf::try_gain(k2)?.cascade(sf::delay())))?;
 
     let plant1 = sf::gain(T)
         .cascade(sf::delay())
-        .cascade(sf::gain(1.0).feedback_add(Some(sf::delay())));
+        .cascade(sf::gain(1.0).try_feedback_add(Some(sf::delay()))?);
     let plant2 = sf::gain(V * T)
         .cascade(sf::delay())
-        .cascade(sf::gain(1.0).feedback_add(Some(sf::delay())));
-    let sf = controller
+        .cascade(sf::gain(1.0).try_feedback_add(Some(sf::delay()))?);
+    controller
         .cascade(plant1)
         .cascade(plant2)
-        .feedback_sub(None);
-    println!("{sf}");
-    sf
+        .try_feedback_sub(None)
 }
 
 pub fn angle_plus_prop_model(k3: f64, k4: f64) -> SystemFunction {
+    try_angle_plus_prop_model(k3, k4).unwrap_or_else(|err| panic!("{err}"))
+}
+
+pub fn try_angle_plus_prop_model(k3: f64, k4: f64) -> Result<SystemFunction, SfError> {
     let plant1 = sf::gain(T)
         .cascade(sf::delay())
-        .cascade(sf::gain(1.0).feedback_add(Some(sf::delay())));
+        .cascade(sf::gain(1.0).try_feedback_add(Some(sf::delay()))?);
 
     let plant2 = sf::gain(V * T)
         .cascade(sf::delay())
-        .cascade(sf::gain(1.0).feedback_add(Some(sf::delay())));
-    let sf = sf::gain(k3)
-        .cascade(plant1.feedback_sub(Some(sf::gain(k4))))
+        .cascade(sf::gain(1.0).try_feedback_add(Some(sf::delay()))?);
+    let sf = sf::try_gain(k3)?
+        .cascade(plant1.try_feedback_sub(Some(sf::try_gain(k4)?))?)
         .cascade(plant2)
-        .feedback_sub(None);
+        .try_feedback_sub(None)?;
     // println!("{sf}");
-    sf
+    Ok(sf)
 }
 
+/// The distance to the wall under the angle plus proportional controller;
+/// prints the error on stderr and returns null if the model is degenerate.
 #[ffi_export]
-fn sig(k3: f64, k4: f64, desired_d: f64) -> repr_c::Box<SignalOpaque<f64>> {
-    Box::new(SignalOpaque {
-        sig: Box::new(
-            angle_plus_prop_model(k3, k4)
-                .into_sm(Some(vec![desired_d, desired_d]), Some(vec![0.503, 0.499]))
-                .transduce_signal(constant(desired_d)),
+fn sig(k3: f64, k4: f64, desired_d: f64) -> Option<repr_c::Box<SignalOpaque<f64>>> {
+    match try_angle_plus_prop_model(k3, k4) {
+        Ok(sf) => Some(
+            Box::new(SignalOpaque {
+                sig: Box::new(
+                    sf.into_sm(Some(vec![desired_d, desired_d]), Some(vec![0.503, 0.499]))
+                        .transduce_signal(constant(desired_d)),
+                ),
+            })
+            .into(),
         ),
-    })
-    .into()
+        Err(err) => {
+            eprintln!("sig({k3}, {k4}, {desired_d}): {err}");
+            None
+        }
+    }
 }
 
 #[ffi_export]
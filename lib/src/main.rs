@@ -1,9 +1,15 @@
 use sm::{
     self,
     opt::{opt_over_line, range},
+    sf::StepMetrics,
 };
 use std::cmp::Ordering;
 
+/// Steps of the simulated step responses.
+const STEPS: usize = 500;
+/// Largest overshoot accepted, in percent.
+const MAX_OVERSHOOT: f64 = 10.0;
+
 fn main() {
     // let k1 = 10.;
     // let k2 = 10.;
@@ -13,13 +19,20 @@ fn main() {
         // for k1 in [300.] {
         // for k1 in [10., 30., 100., 300.] {
         //
+        // fastest to settle among the gains that do not overshoot too much
         let objective = |k4| {
-            let sf = sm::angle_plus_prop_model(k3, k4);
+            let Ok(sf) = sm::try_angle_plus_prop_model(k3, k4) else {
+                return f64::INFINITY;
+            };
             // eprintln!("{k2} => {sf}");
-            let poles = sf.poles();
-            let dominant = poles.dominant();
-            // dbg!(dominant);
-            dominant.magnitude()
+            match sf.step_metrics(STEPS, 0.02) {
+                Some(StepMetrics {
+                    settling_time: Some(steps),
+                    overshoot,
+                    ..
+                }) if overshoot <= MAX_OVERSHOOT => steps as f64,
+                _ => f64::INFINITY,
+            }
         };
 
         let best = opt_over_line(
@@ -30,24 +43,12 @@ fn main() {
         dbg!((k3, best));
         let sf = sm::angle_plus_prop_model(k3, best.1);
         eprintln!("{sf}");
+        eprintln!("{:?}", sf.step_metrics(STEPS, 0.02));
+        let dominant = sf.poles().dominant();
+        eprintln!(
+            "dominant {dominant:?}: period {:?}s, decay time {:?}s",
+            dominant.period(sm::T),
+            dominant.decay_time(sm::T)
+        );
     }
-
-    // for k in [1.0, 10.0, 0.1] {
-    //     let sf = sm::wall_follower_model(k, 0.1, 0.1);
-    //     let poles = sf.poles();
-    //     let dominant = poles.dominant();
-    //     dbg!(dominant);
-
-    //     match dominant {
-    //         sm::sf::Pole::Real(_) => unreachable!(),
-    //         sm::sf::Pole::Complex(re, im) => {
-    //             let omega = im.atan2(re).abs();
-    //             // if omega < 0.0 {
-    //             //     omega += 2.0 * PI
-    //             // };
-    //             let period = 2.0 * PI / omega;
-    //             println!("k={k}, period={period}");
-    //         }
-    //     }
-    // }
 }
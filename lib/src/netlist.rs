@@ -0,0 +1,603 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    V,
+    dyn_sm::BoxedStateMachine,
+    io::{Action, SensorInput},
+    sm::StateMachine,
+    sm_course::{delay, wire},
+    sonars::get_distance_right,
+};
+
+/// Type of the values flowing between netlist nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    Num,
+    Pair(Box<Ty>, Box<Ty>),
+    Sensor,
+    Action,
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ty::Num => write!(f, "num"),
+            Ty::Pair(a, b) => write!(f, "({a}, {b})"),
+            Ty::Sensor => write!(f, "sensor"),
+            Ty::Action => write!(f, "action"),
+        }
+    }
+}
+
+/// A value flowing between netlist nodes. Netlists are type checked when
+/// built, so the accessors below only panic on a bug in this module.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Num(f64),
+    Pair(Box<Value>, Box<Value>),
+    Sensor(SensorInput),
+    Action(Action),
+}
+
+impl Value {
+    fn num(self) -> f64 {
+        match self {
+            Value::Num(x) => x,
+            other => panic!("expected num, found {other:?}"),
+        }
+    }
+
+    fn pair(self) -> (Value, Value) {
+        match self {
+            Value::Pair(a, b) => (*a, *b),
+            other => panic!("expected pair, found {other:?}"),
+        }
+    }
+
+    fn sensor(self) -> SensorInput {
+        match self {
+            Value::Sensor(s) => s,
+            other => panic!("expected sensor, found {other:?}"),
+        }
+    }
+
+    fn action(self) -> Action {
+        match self {
+            Value::Action(a) => a,
+            other => panic!("expected action, found {other:?}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NetlistError {
+    /// The node the error was found in, if any.
+    pub node: Option<String>,
+    pub kind: NetlistErrorKind,
+}
+
+#[derive(Debug)]
+pub enum NetlistErrorKind {
+    Json(serde_json::Error),
+    UnknownNode(String),
+    Cycle,
+    TypeMismatch {
+        expected: Ty,
+        found: Ty,
+    },
+    Arity {
+        expected: &'static str,
+        found: usize,
+    },
+    Invalid(String),
+}
+
+impl Display for NetlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(node) = &self.node {
+            write!(f, "node `{node}`: ")?;
+        }
+        match &self.kind {
+            NetlistErrorKind::Json(err) => write!(f, "{err}"),
+            NetlistErrorKind::UnknownNode(name) => write!(f, "unknown node `{name}`"),
+            NetlistErrorKind::Cycle => write!(f, "node is part of a cycle"),
+            NetlistErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            NetlistErrorKind::Arity { expected, found } => {
+                write!(f, "expected {expected} machines, found {found}")
+            }
+            NetlistErrorKind::Invalid(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for NetlistError {}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Op {
+    Add,
+    Sub,
+}
+
+/// Selects the first machine of a `switch` while the input (or one of its
+/// sonars) lies within the given bounds.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Condition {
+    sonar: Option<usize>,
+    below: Option<f64>,
+    above: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum Node {
+    Wire,
+    Delay {
+        #[serde(default)]
+        init: f64,
+    },
+    #[serde(alias = "gain")]
+    Scale {
+        k: f64,
+    },
+    Constant {
+        value: f64,
+    },
+    Add,
+    Sub,
+    Mul,
+    Sonar {
+        index: usize,
+    },
+    DistanceRight,
+    Action,
+    WallFollower {
+        desired_d: f64,
+        k: f64,
+    },
+    Cascade {
+        machines: Vec<String>,
+    },
+    Parallel {
+        machines: Vec<String>,
+    },
+    FeedbackOp {
+        machine: String,
+        feedback: String,
+        op: Op,
+    },
+    Switch {
+        machines: Vec<String>,
+        when: Condition,
+    },
+    Seq {
+        machines: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNetlist {
+    nodes: HashMap<String, serde_json::Value>,
+    output: String,
+}
+
+type Machine = BoxedStateMachine<Value, Value>;
+
+/// A machine graph read from JSON: named nodes, each a primitive or a
+/// combinator over other nodes, and the `output` node to build.
+///
+/// ```json
+/// {
+///     "nodes": {
+///         "sensor": { "type": "distance_right" },
+///         "controller": { "type": "wall_follower", "desired_d": 0.5, "k": 10 },
+///         "brain": { "type": "cascade", "machines": ["sensor", "controller"] }
+///     },
+///     "output": "brain"
+/// }
+/// ```
+///
+/// A node referenced several times is instantiated once per reference.
+#[derive(Debug)]
+pub struct Netlist {
+    nodes: HashMap<String, Node>,
+    output: String,
+}
+
+impl Netlist {
+    pub fn parse(src: &str) -> Result<Self, NetlistError> {
+        let raw: RawNetlist = serde_json::from_str(src).map_err(|err| NetlistError {
+            node: None,
+            kind: NetlistErrorKind::Json(err),
+        })?;
+        let nodes = raw
+            .nodes
+            .into_iter()
+            .map(|(name, node)| match serde_json::from_value(node) {
+                Ok(node) => Ok((name, node)),
+                Err(err) => Err(NetlistError {
+                    node: Some(name),
+                    kind: NetlistErrorKind::Json(err),
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            nodes,
+            output: raw.output,
+        })
+    }
+
+    /// Builds the output node for inputs of type `input`, returning the
+    /// machine and the type of its outputs.
+    pub fn build(&self, input: &Ty) -> Result<(Machine, Ty), NetlistError> {
+        Builder {
+            nodes: &self.nodes,
+            stack: Vec::new(),
+        }
+        .build(&self.output, input)
+    }
+
+    /// Builds the output node as a brain, from sensor inputs to actions.
+    pub fn brain(&self) -> Result<impl StateMachine<SensorInput, Action> + use<>, NetlistError> {
+        let (machine, out) = self.build(&Ty::Sensor)?;
+        if out != Ty::Action {
+            return Err(NetlistError {
+                node: Some(self.output.clone()),
+                kind: NetlistErrorKind::TypeMismatch {
+                    expected: Ty::Action,
+                    found: out,
+                },
+            });
+        }
+        Ok(Value::Sensor.cascade(machine).cascade(Value::action))
+    }
+}
+
+impl FromStr for Netlist {
+    type Err = NetlistError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+struct Builder<'a> {
+    nodes: &'a HashMap<String, Node>,
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Builder<'a> {
+    fn build(&mut self, name: &str, input: &Ty) -> Result<(Machine, Ty), NetlistError> {
+        let error = |kind| NetlistError {
+            node: Some(name.to_string()),
+            kind,
+        };
+        let (name, node) = self
+            .nodes
+            .get_key_value(name)
+            .ok_or_else(|| error(NetlistErrorKind::UnknownNode(name.to_string())))?;
+        if self.stack.contains(&name.as_str()) {
+            return Err(error(NetlistErrorKind::Cycle));
+        }
+        self.stack.push(name);
+        let built = self.build_node(name, node, input);
+        self.stack.pop();
+        built
+    }
+
+    fn build_node(
+        &mut self,
+        name: &str,
+        node: &'a Node,
+        input: &Ty,
+    ) -> Result<(Machine, Ty), NetlistError> {
+        let error = |kind| NetlistError {
+            node: Some(name.to_string()),
+            kind,
+        };
+        let expect = |expected: Ty, found: &Ty| {
+            if expected == *found {
+                Ok(())
+            } else {
+                Err(error(NetlistErrorKind::TypeMismatch {
+                    expected,
+                    found: found.clone(),
+                }))
+            }
+        };
+        let arity = |machines: &'a [String], expected: &'static str, ok: bool| {
+            if ok {
+                Ok(machines)
+            } else {
+                Err(error(NetlistErrorKind::Arity {
+                    expected,
+                    found: machines.len(),
+                }))
+            }
+        };
+        let num_pair = Ty::Pair(Box::new(Ty::Num), Box::new(Ty::Num));
+
+        Ok(match node {
+            Node::Wire => (wire().boxed(), input.clone()),
+            Node::Delay { init } => {
+                expect(Ty::Num, input)?;
+                (delay(Value::Num(*init)).boxed(), Ty::Num)
+            }
+            Node::Scale { k } => {
+                expect(Ty::Num, input)?;
+                let k = *k;
+                ((move |v: Value| Value::Num(k * v.num())).boxed(), Ty::Num)
+            }
+            Node::Constant { value } => {
+                let value = *value;
+                ((move |_: Value| Value::Num(value)).boxed(), Ty::Num)
+            }
+            Node::Add | Node::Sub | Node::Mul => {
+                expect(num_pair, input)?;
+                let op = match node {
+                    Node::Add => |a, b| a + b,
+                    Node::Sub => |a, b| a - b,
+                    _ => |a, b| a * b,
+                };
+                let machine = move |v: Value| {
+                    let (a, b) = v.pair();
+                    Value::Num(op(a.num(), b.num()))
+                };
+                (machine.boxed(), Ty::Num)
+            }
+            Node::Sonar { index } => {
+                expect(Ty::Sensor, input)?;
+                let index = *index;
+                if index >= 8 {
+                    return Err(error(NetlistErrorKind::Invalid(format!(
+                        "no sonar {index}, the robot has 8"
+                    ))));
+                }
+                (
+                    (move |v: Value| Value::Num(v.sensor().sonars[index])).boxed(),
+                    Ty::Num,
+                )
+            }
+            Node::DistanceRight => {
+                expect(Ty::Sensor, input)?;
+                let machine = |v: Value| Value::Num(get_distance_right(&v.sensor().sonars));
+                (machine.boxed(), Ty::Num)
+            }
+            Node::Action => {
+                expect(num_pair, input)?;
+                let machine = |v: Value| {
+                    let (fvel, rvel) = v.pair();
+                    Value::Action(Action {
+                        fvel: fvel.num(),
+                        rvel: rvel.num(),
+                    })
+                };
+                (machine.boxed(), Ty::Action)
+            }
+            Node::WallFollower { desired_d, k } => {
+                expect(Ty::Num, input)?;
+                let (desired_d, k) = (*desired_d, *k);
+                let machine = move |v: Value| {
+                    Value::Action(Action {
+                        fvel: V,
+                        rvel: k * (desired_d - v.num()),
+                    })
+                };
+                (machine.boxed(), Ty::Action)
+            }
+            Node::Cascade { machines } => {
+                let machines = arity(machines, "at least 2", machines.len() >= 2)?;
+                let (mut machine, mut ty) = self.build(&machines[0], input)?;
+                for next in &machines[1..] {
+                    let (next, next_ty) = self.build(next, &ty)?;
+                    machine = machine.cascade(next).boxed();
+                    ty = next_ty;
+                }
+                (machine, ty)
+            }
+            Node::Parallel { machines } => {
+                let machines = arity(machines, "2", machines.len() == 2)?;
+                let (m1, t1) = self.build(&machines[0], input)?;
+                let (m2, t2) = self.build(&machines[1], input)?;
+                let machine = m1
+                    .parallel(m2)
+                    .cascade(|(a, b)| Value::Pair(Box::new(a), Box::new(b)));
+                (machine.boxed(), Ty::Pair(Box::new(t1), Box::new(t2)))
+            }
+            Node::FeedbackOp {
+                machine,
+                feedback,
+                op,
+            } => {
+                expect(Ty::Num, input)?;
+                let (machine, out) = self.build(machine, &Ty::Num)?;
+                let (feedback, back) = self.build(feedback, &out)?;
+                if back != Ty::Num {
+                    return Err(error(NetlistErrorKind::TypeMismatch {
+                        expected: Ty::Num,
+                        found: back,
+                    }));
+                }
+                let op = *op;
+                let machine = machine.feedback_op(feedback, move |a: Value, b: Value| {
+                    Value::Num(match op {
+                        Op::Add => a.num() + b.num(),
+                        Op::Sub => a.num() - b.num(),
+                    })
+                });
+                machine
+                    .check_loop()
+                    .map_err(|err| error(NetlistErrorKind::Invalid(err.to_string())))?;
+                (machine.boxed(), out)
+            }
+            Node::Switch { machines, when } => {
+                let machines = arity(machines, "2", machines.len() == 2)?;
+                let Condition {
+                    sonar,
+                    below,
+                    above,
+                } = when.clone();
+                match sonar {
+                    Some(index) if index >= 8 => {
+                        return Err(error(NetlistErrorKind::Invalid(format!(
+                            "no sonar {index}, the robot has 8"
+                        ))));
+                    }
+                    Some(_) => expect(Ty::Sensor, input)?,
+                    None => expect(Ty::Num, input)?,
+                }
+                let (m1, t1) = self.build(&machines[0], input)?;
+                let (m2, t2) = self.build(&machines[1], input)?;
+                expect(t1.clone(), &t2)?;
+                let pred = move |v: Value| {
+                    let x = match sonar {
+                        Some(index) => v.sensor().sonars[index],
+                        None => v.num(),
+                    };
+                    below.is_none_or(|b| x < b) && above.is_none_or(|a| x > a)
+                };
+                (m1.switch(m2, pred).boxed(), t1)
+            }
+            Node::Seq { machines } => {
+                let machines = arity(machines, "at least 2", machines.len() >= 2)?;
+                let (mut machine, ty) = self.build(&machines[0], input)?;
+                for next in &machines[1..] {
+                    let (next, next_ty) = self.build(next, input)?;
+                    expect(ty.clone(), &next_ty)?;
+                    machine = machine.seq(next).boxed();
+                }
+                (machine, ty)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        io::SensorInput,
+        netlist::{Netlist, NetlistErrorKind, Ty, Value},
+        sm::StateMachine,
+    };
+
+    const WALL_FOLLOWER: &str = r#"{
+        "nodes": {
+            "sensor": { "type": "distance_right" },
+            "controller": { "type": "wall_follower", "desired_d": 0.5, "k": 2 },
+            "brain": { "type": "cascade", "machines": ["sensor", "controller"] }
+        },
+        "output": "brain"
+    }"#;
+
+    #[test]
+    fn test_wall_follower() {
+        let brain = Netlist::parse(WALL_FOLLOWER).unwrap().brain().unwrap();
+        let input = SensorInput {
+            sonars: [1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 0.7],
+            ..Default::default()
+        };
+        let actions = brain.transduce([input]).collect::<Vec<_>>();
+        let d = crate::sonars::get_distance_right(&[1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 0.7]);
+        assert_eq!(actions.len(), 1);
+        assert!((actions[0].fvel - crate::V).abs() < 1e-12);
+        assert!((actions[0].rvel - 2.0 * (0.5 - d)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_numeric_graph() {
+        let netlist = Netlist::parse(
+            r#"{
+                "nodes": {
+                    "acc": { "type": "feedback_op", "machine": "id", "feedback": "z", "op": "add" },
+                    "id": { "type": "wire" },
+                    "z": { "type": "delay" },
+                    "double": { "type": "gain", "k": 2 },
+                    "both": { "type": "parallel", "machines": ["acc", "double"] },
+                    "out": { "type": "cascade", "machines": ["both", "add"] },
+                    "add": { "type": "add" }
+                },
+                "output": "out"
+            }"#,
+        )
+        .unwrap();
+        let (machine, out) = netlist.build(&Ty::Num).unwrap();
+        assert_eq!(out, Ty::Num);
+        let outputs = machine
+            .transduce([1.0, 2.0, 3.0].map(Value::Num))
+            .map(|v| v.num())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, vec![3.0, 7.0, 12.0]);
+    }
+
+    #[test]
+    fn test_errors_name_the_node() {
+        let err = |src: &str| {
+            Netlist::parse(src)
+                .and_then(|n| n.brain().map(|_| ()))
+                .unwrap_err()
+        };
+
+        let e = err(r#"{
+            "nodes": {
+                "scale": { "type": "scale", "k": 2 },
+                "brain": { "type": "cascade", "machines": ["scale", "scale"] }
+            },
+            "output": "brain"
+        }"#);
+        assert_eq!(e.node.as_deref(), Some("scale"));
+        assert!(matches!(e.kind, NetlistErrorKind::TypeMismatch { .. }));
+        assert_eq!(e.to_string(), "node `scale`: expected num, found sensor");
+
+        let e = err(r#"{
+            "nodes": { "brain": { "type": "parallel", "machines": ["brain"] } },
+            "output": "brain"
+        }"#);
+        assert!(matches!(e.kind, NetlistErrorKind::Arity { found: 1, .. }));
+
+        let e = err(r#"{
+            "nodes": { "brain": { "type": "seq", "machines": ["brain", "brain"] } },
+            "output": "brain"
+        }"#);
+        assert!(matches!(e.kind, NetlistErrorKind::Cycle));
+
+        let e = err(r#"{
+            "nodes": { "brain": { "type": "cascade", "machines": ["sensor", "nope"] },
+                       "sensor": { "type": "distance_right" } },
+            "output": "brain"
+        }"#);
+        assert_eq!(e.node.as_deref(), Some("nope"));
+        assert!(matches!(e.kind, NetlistErrorKind::UnknownNode(_)));
+
+        let e = err(r#"{ "nodes": { "brain": { "type": "delay", "k": 1 } }, "output": "brain" }"#);
+        assert_eq!(e.node.as_deref(), Some("brain"));
+        assert!(matches!(e.kind, NetlistErrorKind::Json(_)));
+
+        let e = err(r#"{
+            "nodes": {
+                "brain": { "type": "cascade", "machines": ["sensor", "loop", "controller"] },
+                "sensor": { "type": "distance_right" },
+                "loop": { "type": "feedback_op", "machine": "id", "feedback": "id", "op": "sub" },
+                "id": { "type": "wire" },
+                "controller": { "type": "wall_follower", "desired_d": 0.5, "k": 2 }
+            },
+            "output": "brain"
+        }"#);
+        assert_eq!(e.node.as_deref(), Some("loop"));
+        assert!(matches!(e.kind, NetlistErrorKind::Invalid(_)));
+
+        let e = err(WALL_FOLLOWER
+            .replace("\"controller\"]", "\"sensor\"]")
+            .as_str());
+        assert_eq!(e.to_string(), "node `sensor`: expected sensor, found num");
+    }
+}
@@ -1,11 +1,14 @@
 use std::{
     cmp,
+    f64::consts::PI,
     fmt::Display,
-    ops::{Add, Mul, Sub},
+    iter,
+    ops::{Add, Mul, Neg, Sub},
 };
 
+use crate::complex::Complex;
 #[cfg(feature = "poles")]
-use faer::{Mat, complex::Complex};
+use faer::Mat;
 
 #[derive(Clone)]
 pub struct Poly {
@@ -59,19 +62,52 @@ impl<'a, const V: char> Display for DispPoly<'a, V> {
     }
 }
 
+/// Why a polynomial could not be built or solved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolyError {
+    /// No coefficients, or only zeros.
+    Zero,
+    /// The root finder failed, e.g. on non-finite coefficients.
+    Roots,
+}
+
+impl Display for PolyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolyError::Zero => write!(f, "zero poly is not supported"),
+            PolyError::Roots => write!(f, "cannot find the roots"),
+        }
+    }
+}
+
+impl std::error::Error for PolyError {}
+
 impl Poly {
+    /// Leading zero coefficients are dropped, so `Poly::new([0.0, 1.0])` is
+    /// the constant 1; only an all-zero polynomial panics.
+    #[track_caller]
     pub fn new<const N: usize>(coeffs: [f64; N]) -> Self {
-        assert!(coeffs[0] != 0.0, "zero poly is not supported");
-        assert!(N != 0);
-        Self {
-            coeffs: coeffs.into(),
-        }
+        Self::try_new(coeffs).unwrap_or_else(|err| panic!("{err}"))
     }
 
+    /// Drops leading zero coefficients like `new`.
+    #[track_caller]
     pub fn from_vec(coeffs: Vec<f64>) -> Self {
-        assert!(coeffs[0] != 0.0, "zero poly is not supported");
-        assert!(coeffs.len() != 0);
-        Self { coeffs }
+        Self::try_from_vec(coeffs).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_new<const N: usize>(coeffs: [f64; N]) -> Result<Self, PolyError> {
+        Self::try_from_vec(coeffs.into())
+    }
+
+    /// Drops the leading zero coefficients, failing if nothing is left.
+    pub fn try_from_vec(mut coeffs: Vec<f64>) -> Result<Self, PolyError> {
+        let zeros = coeffs.iter().take_while(|c| **c == 0.0).count();
+        if zeros == coeffs.len() {
+            return Err(PolyError::Zero);
+        }
+        coeffs.drain(..zeros);
+        Ok(Self { coeffs })
     }
 
     pub fn coeffs(self) -> Vec<f64> {
@@ -87,8 +123,26 @@ impl Poly {
         Poly { coeffs }
     }
 
+    /// The roots, repeated roots included; see `try_roots`.
+    #[track_caller]
+    pub fn roots(&self) -> Vec<Complex> {
+        self.try_roots().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// The roots, repeated roots included, from the eigenvalues of the
+    /// companion matrix with the `poles` feature and from `aberth_roots`
+    /// otherwise.
+    pub fn try_roots(&self) -> Result<Vec<Complex>, PolyError> {
+        let roots = self.solve()?;
+        if roots.iter().all(|z| z.is_finite()) {
+            Ok(roots)
+        } else {
+            Err(PolyError::Roots)
+        }
+    }
+
     #[cfg(feature = "poles")]
-    pub fn roots(&self) -> Vec<Complex<f64>> {
+    fn solve(&self) -> Result<Vec<Complex>, PolyError> {
         // Build the companion matrix (n-1 x n-1)
         let n = self.coeffs.len();
         let mut c = Mat::<f64>::zeros(n - 1, n - 1);
@@ -105,8 +159,197 @@ impl Poly {
         }
 
         // Compute eigenvalues — these are the polynomial roots
-        c.eigenvalues().unwrap()
+        let roots = c.eigenvalues().map_err(|_| PolyError::Roots)?;
+        Ok(roots
+            .into_iter()
+            .map(|z| Complex::new(z.re, z.im))
+            .collect())
+    }
+
+    #[cfg(not(feature = "poles"))]
+    fn solve(&self) -> Result<Vec<Complex>, PolyError> {
+        Ok(self.aberth_roots())
+    }
+
+    /// `self + rhs`, failing when it cancels out to the zero poly.
+    pub fn try_add(self, rhs: Poly) -> Result<Poly, PolyError> {
+        let self_len = self.coeffs.len();
+        let rhs_len = rhs.coeffs.len();
+        let max_len = cmp::max(self_len, rhs_len);
+        let min_len = cmp::min(self_len, rhs_len);
+        let mut sum = Vec::with_capacity(max_len);
+
+        if min_len != max_len {
+            sum.resize(max_len - min_len, 0.0);
+        }
+
+        let other = if self_len < rhs_len {
+            sum.extend_from_slice(&self.coeffs);
+            &rhs.coeffs
+        } else {
+            sum.extend_from_slice(&rhs.coeffs);
+            &self.coeffs
+        };
+
+        for (s, o) in sum.iter_mut().zip(other.iter()) {
+            *s += o;
+        }
+
+        Poly::try_from_vec(sum)
+    }
+
+    /// `self - rhs`, failing when it cancels out to the zero poly.
+    pub fn try_sub(self, rhs: Poly) -> Result<Poly, PolyError> {
+        self.try_add(-rhs)
+    }
+
+    /// The value at `x`.
+    pub fn eval(&self, x: f64) -> f64 {
+        self.coeffs.iter().fold(0.0, |acc, c| acc * x + c)
+    }
+
+    pub fn eval_complex(&self, z: Complex) -> Complex {
+        eval_with_derivative(&self.coeffs, z).0
+    }
+
+    /// The roots found by Aberth's method and polished with Newton steps.
+    /// Close roots are taken for a repeated root when the polynomial's
+    /// derivatives vanish there too, and real roots have an imaginary part
+    /// of exactly 0.
+    pub fn aberth_roots(&self) -> Vec<Complex> {
+        // roots at 0 are exact
+        let zeros = self.coeffs.iter().rev().take_while(|c| **c == 0.0).count();
+        let mut roots = vec![Complex::default(); zeros];
+        let coeffs = self.coeffs[..self.coeffs.len() - zeros]
+            .iter()
+            .map(|c| c / self.coeffs[0])
+            .collect::<Vec<_>>();
+        let n = coeffs.len() - 1;
+        if n == 0 {
+            return roots;
+        }
+
+        // start spread over a circle as large as the largest root can be
+        let radius = coeffs
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, c)| c.abs().powf(1.0 / k as f64))
+            .fold(0.0, f64::max);
+        let mut z = (0..n)
+            .map(|k| Complex::from_polar(radius, 2.0 * PI * k as f64 / n as f64 + 0.4))
+            .collect::<Vec<_>>();
+
+        for _ in 0..ABERTH_MAX_ITER {
+            let mut converged = true;
+            for k in 0..n {
+                let (p, dp) = eval_with_derivative(&coeffs, z[k]);
+                let repulsion = (0..n)
+                    .filter(|&j| j != k)
+                    .fold(Complex::default(), |acc, j| {
+                        acc + Complex::from(1.0) / (z[k] - z[j])
+                    });
+                let denom = dp - p * repulsion;
+                if p == Complex::default() || denom == Complex::default() {
+                    continue;
+                }
+                let step = p / denom;
+                z[k] = z[k] - step;
+                converged &= step.norm() <= f64::EPSILON * z[k].norm().max(1.0);
+            }
+            if converged {
+                break;
+            }
+        }
+
+        // a root of multiplicity m only converges to about the m-th root of
+        // the precision. Take a root and its m - 1 nearest for a repeated
+        // one, trying the largest m first, when the polynomial and its
+        // derivatives up to the (m - 1)-th all vanish, to within rounding
+        // error, at the root of the last one: distinct roots close together
+        // stay apart however close they are
+        let derivatives = iter::successors(Some(coeffs.clone()), |c| Some(derive(c)))
+            .take(n)
+            .collect::<Vec<_>>();
+        let mut taken = vec![false; n];
+        for k in 0..n {
+            if taken[k] {
+                continue;
+            }
+            let mut near = iter::once(k)
+                .chain((k + 1..n).filter(|&j| !taken[j]))
+                .collect::<Vec<_>>();
+            near.sort_by(|&i, &j| (z[i] - z[k]).norm().total_cmp(&(z[j] - z[k]).norm()));
+            let (cluster, mut root) = (1..=near.len())
+                .rev()
+                .find_map(|m| {
+                    let mean = near[..m]
+                        .iter()
+                        .fold(Complex::default(), |acc, &j| acc + z[j])
+                        * (1.0 / m as f64);
+                    let root = newton(&derivatives[m - 1], mean);
+                    derivatives[..m]
+                        .iter()
+                        .all(|c| vanishes_at(c, root))
+                        .then_some((&near[..m], root))
+                })
+                .unwrap_or_else(|| (&near[..1], newton(&coeffs, z[k])));
+            if root.im.abs() <= 1e-9 * root.norm().max(1.0) {
+                root.im = 0.0;
+            }
+            for &j in cluster {
+                taken[j] = true;
+                roots.push(root);
+            }
+        }
+        roots
+    }
+}
+
+const ABERTH_MAX_ITER: usize = 500;
+
+/// Whether `|p(z)|` is within a few times the rounding error bound of
+/// evaluating it by Horner's scheme.
+fn vanishes_at(coeffs: &[f64], z: Complex) -> bool {
+    let bound = coeffs.iter().fold(0.0, |acc, c| acc * z.norm() + c.abs());
+    eval_with_derivative(coeffs, z).0.norm() <= 64.0 * f64::EPSILON * bound
+}
+
+/// Polishes the approximate root `z` of `coeffs` with Newton steps, for as
+/// long as they bring `|p(z)|` down.
+fn newton(coeffs: &[f64], mut z: Complex) -> Complex {
+    for _ in 0..10 {
+        let (p, dp) = eval_with_derivative(coeffs, z);
+        if dp == Complex::default() {
+            break;
+        }
+        let next = z - p / dp;
+        if eval_with_derivative(coeffs, next).0.norm() >= p.norm() {
+            break;
+        }
+        z = next;
     }
+    z
+}
+
+fn derive(coeffs: &[f64]) -> Vec<f64> {
+    let n = coeffs.len() - 1;
+    coeffs[..n]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (n - i) as f64)
+        .collect()
+}
+
+/// `p(z)` and `p'(z)` by Horner's scheme, `coeffs` highest degree first.
+fn eval_with_derivative(coeffs: &[f64], z: Complex) -> (Complex, Complex) {
+    let mut p = Complex::default();
+    let mut dp = Complex::default();
+    for &c in coeffs {
+        dp = dp * z + p;
+        p = p * z + Complex::from(c);
+    }
+    (p, dp)
 }
 
 impl Mul<Poly> for Poly {
@@ -133,68 +376,37 @@ impl Mul<Poly> for Poly {
 impl Add<Poly> for Poly {
     type Output = Poly;
 
+    #[track_caller]
     fn add(self, rhs: Poly) -> Self::Output {
-        let self_len = self.coeffs.len();
-        let rhs_len = rhs.coeffs.len();
-        let max_len = cmp::max(self_len, rhs_len);
-        let min_len = cmp::min(self_len, rhs_len);
-        let mut sum = Vec::with_capacity(max_len);
-
-        if min_len != max_len {
-            sum.resize(max_len - min_len, 0.0);
-        }
-
-        let other = if self_len < rhs_len {
-            sum.extend_from_slice(&self.coeffs);
-            &rhs.coeffs
-        } else {
-            sum.extend_from_slice(&rhs.coeffs);
-            &self.coeffs
-        };
-
-        for (s, o) in sum.iter_mut().zip(other.iter()) {
-            *s += o;
-        }
-
-        Poly::from_vec(sum)
+        self.try_add(rhs).unwrap_or_else(|err| panic!("{err}"))
     }
 }
 
 impl Sub<Poly> for Poly {
     type Output = Poly;
 
+    #[track_caller]
     fn sub(self, rhs: Poly) -> Self::Output {
-        let self_len = self.coeffs.len();
-        let rhs_len = rhs.coeffs.len();
-        let max_len = cmp::max(self_len, rhs_len);
-        let min_len = cmp::min(self_len, rhs_len);
-        let mut sum = Vec::with_capacity(max_len);
-
-        if min_len != max_len {
-            sum.resize(max_len - min_len, 0.0);
-        }
+        self.try_sub(rhs).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
 
-        let other = if self_len < rhs_len {
-            sum.extend_from_slice(&self.coeffs);
-            &rhs.coeffs
-        } else {
-            sum.extend_from_slice(&rhs.coeffs);
-            &self.coeffs
-        };
+impl Neg for Poly {
+    type Output = Poly;
 
-        for (s, o) in sum.iter_mut().zip(other.iter()) {
-            *s -= o;
+    fn neg(self) -> Self::Output {
+        Poly {
+            coeffs: self.coeffs.into_iter().map(|c| -c).collect(),
         }
-
-        Poly::from_vec(sum)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::poly::{DispPoly, Poly};
-    use faer::complex::Complex;
-    use std::cmp::Ordering;
+    use crate::{
+        complex::Complex,
+        poly::{DispPoly, Poly, PolyError},
+    };
 
     fn real_roots(p: Poly) -> Vec<f64> {
         let roots = p.roots();
@@ -209,12 +421,14 @@ mod tests {
         roots
     }
 
-    fn complex_roots(p: Poly) -> Vec<Complex<f64>> {
-        let mut roots = p.roots();
+    fn complex_roots(p: Poly) -> Vec<Complex> {
+        sorted(p.roots())
+    }
+
+    fn sorted(mut roots: Vec<Complex>) -> Vec<Complex> {
         roots.sort_by(|c1, c2| match c1.re.total_cmp(&c2.re) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => c1.im.total_cmp(&c2.im),
+            _ if (c1.re - c2.re).abs() <= 10e-9 => c1.im.total_cmp(&c2.im),
+            ordering => ordering,
         });
         roots
     }
@@ -229,7 +443,7 @@ right: {right_val:?}"#
         }
     }
 
-    fn complex_float_assert(left_val: Complex<f64>, right_val: Complex<f64>) {
+    fn complex_float_assert(left_val: Complex, right_val: Complex) {
         float_assert_eq(left_val.re, right_val.re);
         float_assert_eq(left_val.im, right_val.im);
     }
@@ -281,6 +495,133 @@ right: {right_val:?}"#
         float_assert_eq(-1.0, roots[1]);
     }
 
+    #[test]
+    fn aberth_higher_degrees() {
+        let roots = sorted(Poly::new([1.0, -10.0, 35.0, -50.0, 24.0]).aberth_roots());
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            complex_float_assert(Complex::from(expected), *root);
+        }
+
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let roots = sorted(Poly::new([1.0, 0.0, 0.0, 0.0, 1.0]).aberth_roots());
+        for (root, (re, im)) in roots.iter().zip([(-h, -h), (-h, h), (h, -h), (h, h)]) {
+            complex_float_assert(Complex::new(re, im), *root);
+        }
+
+        let roots = sorted(Poly::new([1.0, 3.0, 3.0, 1.0]).aberth_roots());
+        assert_eq!(roots.len(), 3);
+        for root in roots {
+            assert_eq!(root.im, 0.0);
+            float_assert_eq(-1.0, root.re);
+        }
+
+        let roots = sorted(Poly::new([2.0, -2.0, 0.0, 0.0]).aberth_roots());
+        assert_eq!(roots, [0.0, 0.0, 1.0].map(Complex::from));
+    }
+
+    #[test]
+    fn aberth_close_and_repeated_roots() {
+        // distinct roots closer together than repeated ones converge
+        let p = Poly::new([1.0, -1.0]) * Poly::new([1.0, -1.00001]);
+        let roots = sorted(p.aberth_roots());
+        assert!((roots[0].re - 1.0).abs() < 1e-9, "{roots:?}");
+        assert!((roots[1].re - 1.00001).abs() < 1e-9, "{roots:?}");
+
+        for m in 4..=6 {
+            let p = (1..m).fold(Poly::new([1.0, 1.0]), |p, _| p * Poly::new([1.0, 1.0]));
+            let roots = p.aberth_roots();
+            assert_eq!(roots, vec![Complex::from(-1.0); m], "multiplicity {m}");
+        }
+
+        // a double root next to a simple one
+        let p = Poly::new([1.0, -2.0, 1.0]) * Poly::new([1.0, -1.001]);
+        let roots = sorted(p.aberth_roots());
+        assert_eq!(roots[0], roots[1]);
+        complex_float_assert(Complex::from(1.0), roots[0]);
+        complex_float_assert(Complex::from(1.001), roots[2]);
+    }
+
+    #[test]
+    fn aberth_closed_form() {
+        // 0.63 x^2 - 1.6 x + 1 = (0.9 x - 1) (0.7 x - 1)
+        let roots = sorted(Poly::new([0.63, -1.6, 1.0]).aberth_roots());
+        complex_float_assert(Complex::from(1.0 / 0.9), roots[0]);
+        complex_float_assert(Complex::from(1.0 / 0.7), roots[1]);
+
+        // 1.1 x^2 - 1.9 x + 1, a conjugate pair
+        let im = 0.79f64.sqrt() / 2.2;
+        let roots = sorted(Poly::new([1.1, -1.9, 1.0]).aberth_roots());
+        complex_float_assert(Complex::new(1.9 / 2.2, -im), roots[0]);
+        complex_float_assert(Complex::new(1.9 / 2.2, im), roots[1]);
+
+        // (x - 2)^2 (x + 1) (x^2 + 2x + 5): a double root, a simple one and
+        // the pair -1 ± 2i
+        let p = Poly::new([1.0, -2.0])
+            * Poly::new([1.0, -2.0])
+            * Poly::new([1.0, 1.0])
+            * Poly::new([1.0, 2.0, 5.0]);
+        let roots = sorted(p.aberth_roots());
+        let expected = [
+            Complex::new(-1.0, -2.0),
+            Complex::from(-1.0),
+            Complex::new(-1.0, 2.0),
+            Complex::from(2.0),
+            Complex::from(2.0),
+        ];
+        assert_eq!(roots.len(), expected.len());
+        for (root, expected) in roots.into_iter().zip(expected) {
+            complex_float_assert(expected, root);
+        }
+    }
+
+    #[cfg(feature = "poles")]
+    #[test]
+    fn aberth_matches_companion() {
+        for coeffs in [
+            vec![2.0, -4.0],
+            vec![1.0, -5.0, 6.0],
+            vec![1.0, 4.0, 5.0],
+            vec![1.0, 2.0, 1.0],
+            vec![0.63, -1.6, 1.0],
+            vec![1.1, -1.9, 1.0],
+            vec![1.0, -0.3, 0.5, -0.2, 0.1],
+        ] {
+            let p = Poly::from_vec(coeffs);
+            let aberth = sorted(p.aberth_roots());
+            let companion = sorted(p.roots());
+            assert_eq!(aberth.len(), companion.len());
+            for (a, c) in aberth.into_iter().zip(companion) {
+                complex_float_assert(c, a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sub() {
+        vec_float_assert_eq(&(Poly::new([1.0]) - Poly::new([0.5])).coeffs, &[0.5]);
+        vec_float_assert_eq(
+            &(Poly::new([1.0, 0.0]) - Poly::new([2.0])).coeffs,
+            &[1.0, -2.0],
+        );
+        vec_float_assert_eq(
+            &(Poly::new([3.0]) - Poly::new([1.0, 1.0])).coeffs,
+            &[-1.0, 2.0],
+        );
+        assert_eq!(Poly::new([2.0, -1.0, 3.0]).eval(2.0), 9.0);
+    }
+
+    #[test]
+    fn test_zero() {
+        assert_eq!(Poly::try_new([0.0, 0.0]).err(), Some(PolyError::Zero));
+        assert_eq!(Poly::try_from_vec(vec![]).err(), Some(PolyError::Zero));
+        assert_eq!(Poly::try_new([0.0, 2.0, 1.0]).unwrap().coeffs, [2.0, 1.0]);
+        assert_eq!(Poly::new([0.0, 1.0]).coeffs, [1.0]);
+        let cancel = Poly::new([1.0, 2.0]).try_sub(Poly::new([1.0, 2.0]));
+        assert_eq!(cancel.err(), Some(PolyError::Zero));
+        let nan = Poly::new([1.0, f64::NAN]).try_roots();
+        assert_eq!(nan.err(), Some(PolyError::Roots));
+    }
+
     #[test]
     fn test_mul() {
         macro_rules! test_mul {
@@ -323,6 +664,7 @@ right: {right_val:?}"#
         test_add!([1.0], [1.0], [2.0]);
         test_add!([1.0], [2.0], [3.0]);
         // test_add!([1.0], [-1.0], [0.0]); zero poly is not supported
+        test_add!([1.0, 1.0], [-1.0, 0.0], [1.0]);
         test_add!([1.0, 0.0], [2.0], [1.0, 2.0]);
         test_add!([1.0, 0.0], [1.0, 0.0], [2.0, 0.0]);
         test_add!([1.0, -1.0], [1.0, -1.0], [2.0, -2.0]);
@@ -0,0 +1,230 @@
+use std::iter;
+
+use crate::{
+    behaviours::{DISTANCE_EPS, DynamicMoveToPoint},
+    grid_map::{Cell, GridMap, MapMaker},
+    io::{Action, Point, SensorInput},
+    search::a_star,
+    sm::StateMachine,
+    sm_course::wire,
+};
+
+/// lib601's dynamic replanner: maps what the sonars see, plans a path to
+/// `goal` over the map with A* and outputs the next waypoint along it,
+/// planning again whenever the map shows the path blocked. Unseen cells
+/// count as free.
+pub struct Replanner {
+    pub goal: Point,
+    pub map_maker: MapMaker,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplannerState {
+    pub map: GridMap,
+    /// Corners of the current path, ending at the goal; empty when no path
+    /// was found.
+    pub waypoints: Vec<Point>,
+    pub arrived: bool,
+}
+
+impl Replanner {
+    /// Plans over `map` for the Pioneer.
+    pub fn new(goal: Point, map: GridMap) -> Self {
+        Self {
+            goal,
+            map_maker: MapMaker::new(map),
+        }
+    }
+
+    /// Whether the robot fits in `cell` without touching an occupied one.
+    fn fits(&self, map: &GridMap, cell: Cell) -> bool {
+        map.is_clear(cell, self.map_maker.robot.radius)
+    }
+
+    /// Whether the robot can still drive from `pos` through `waypoints`,
+    /// ignoring the cell it is in.
+    fn is_open(&self, map: &GridMap, pos: Point, waypoints: &[Point]) -> bool {
+        iter::once(pos)
+            .chain(waypoints.iter().copied())
+            .zip(waypoints)
+            .enumerate()
+            .all(|(k, (from, to))| {
+                map.ray_cells(from, *to)
+                    .into_iter()
+                    .skip(usize::from(k == 0))
+                    .all(|cell| self.fits(map, cell))
+            })
+    }
+
+    /// Moves to the 8 neighbours the robot fits in, diagonals only when
+    /// both cells beside them are free too.
+    fn moves(&self, map: &GridMap, (i, j): Cell) -> Vec<((i64, i64), Cell, f64)> {
+        let open = |di: i64, dj: i64| {
+            let step = Point::new(di as f64 * map.resolution, dj as f64 * map.resolution);
+            map.cell(map.cell_center((i, j)) + step)
+                .filter(|&cell| self.fits(map, cell))
+        };
+        let mut moves = vec![];
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                if (di, dj) == (0, 0) {
+                    continue;
+                }
+                let diagonal = di != 0 && dj != 0;
+                if diagonal && (open(di, 0).is_none() || open(0, dj).is_none()) {
+                    continue;
+                }
+                if let Some(cell) = open(di, dj) {
+                    let cost = if diagonal {
+                        std::f64::consts::SQRT_2
+                    } else {
+                        1.0
+                    };
+                    moves.push(((di, dj), cell, cost * map.resolution));
+                }
+            }
+        }
+        moves
+    }
+
+    fn plan(&self, map: &GridMap, pos: Point) -> Vec<Point> {
+        let (Some(start), Some(goal)) = (map.cell(pos), map.cell(self.goal)) else {
+            return vec![];
+        };
+        let outcome = a_star(
+            start,
+            |&cell| cell == goal,
+            |&cell| self.moves(map, cell),
+            |&cell| map.cell_center(cell).distance_to(self.goal),
+            map.width * map.height,
+        );
+        let Some(path) = outcome.path else {
+            return vec![];
+        };
+        // keep the cells where the path turns
+        let mut waypoints = path
+            .steps
+            .iter()
+            .zip(path.steps.iter().skip(1))
+            .filter(|((dir, _), (next, _))| dir != next)
+            .map(|((_, cell), _)| map.cell_center(*cell))
+            .collect::<Vec<_>>();
+        waypoints.push(self.goal);
+        waypoints
+    }
+}
+
+impl StateMachine<SensorInput, Point> for Replanner {
+    type State = ReplannerState;
+
+    fn start_state(&self) -> Self::State {
+        ReplannerState {
+            map: self.map_maker.start_state(),
+            waypoints: vec![],
+            arrived: false,
+        }
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        state.arrived
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<SensorInput>,
+    ) -> (Self::State, Option<Point>) {
+        let Some(input) = input else {
+            return (state, None);
+        };
+        let pos = input.odometry.pos;
+        let (map, _) = self.map_maker.next_values(state.map, Some(input));
+        let mut waypoints = state.waypoints;
+        while waypoints.len() > 1 && waypoints[0].is_near(pos, map.resolution / 2.0) {
+            waypoints.remove(0);
+        }
+        if waypoints.is_empty() || !self.is_open(&map, pos, &waypoints) {
+            waypoints = self.plan(&map, pos);
+        }
+        // stay put while there is no way through
+        let target = waypoints.first().copied().unwrap_or(pos);
+        let state = ReplannerState {
+            map,
+            waypoints,
+            arrived: pos.is_near(self.goal, DISTANCE_EPS),
+        };
+        (state, Some(target))
+    }
+}
+
+/// Drives to `goal` around the obstacles it discovers on `map`.
+pub fn replanner(goal: Point, map: GridMap) -> impl StateMachine<SensorInput, Action> {
+    Replanner::new(goal, map)
+        .zip_with(wire(), |target, sensors| (target, sensors))
+        .cascade(DynamicMoveToPoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid_map::GridMap,
+        io::Pose,
+        sim::{Simulator, Wall},
+        sm::StateFullMachine,
+        world::World,
+    };
+
+    fn distance_to_wall(p: Point, wall: &Wall) -> f64 {
+        let edge = wall.end - wall.start;
+        let to_p = p - wall.start;
+        let t = ((to_p.x * edge.x + to_p.y * edge.y) / (edge.x * edge.x + edge.y * edge.y))
+            .clamp(0.0, 1.0);
+        p.distance_to(wall.start + Point::new(edge.x * t, edge.y * t))
+    }
+
+    #[test]
+    fn test_drives_around_unseen_wall() {
+        let world =
+            World::parse("dimensions(4, 4)\nwall((2, 0.8), (2, 3))\ninitialRobotLoc(0.5, 2)\n")
+                .unwrap();
+        let mut sim = Simulator::from_world(&world);
+        let goal = Point::new(3.5, 2.0);
+        let mut brain = replanner(goal, GridMap::from_world(&world, 0.1)).into_state_full_machine();
+        let trajectory = sim.run(&mut brain, 3000);
+        assert!(brain.is_done(), "stuck at {:?}", sim.pose());
+        assert!(sim.pose().pos.is_near(goal, DISTANCE_EPS));
+        let closest = trajectory
+            .iter()
+            .map(|pose| distance_to_wall(pose.pos, &world.walls[0]))
+            .fold(f64::INFINITY, f64::min);
+        assert!(closest > 0.1, "came within {closest} of the wall");
+    }
+
+    #[test]
+    fn test_replans_when_blocked() {
+        let map = GridMap::new(Point::new(0.0, 0.0), Point::new(4.0, 4.0), 0.1);
+        let planner = Replanner::new(Point::new(3.5, 2.0), map);
+        let at = |x| {
+            let pose = Pose {
+                pos: Point::new(x, 2.0),
+                theta: 0.0,
+            };
+            Simulator::new(
+                pose,
+                vec![Wall::new(Point::new(2.0, 1.0), Point::new(2.0, 3.0))],
+            )
+            .sensor_input()
+        };
+        // too far to see the wall: straight to the goal
+        let (state, target) = planner.next_values(planner.start_state(), Some(at(0.0)));
+        assert_eq!(state.waypoints.len(), 1);
+        assert!(target.unwrap().is_near(Point::new(3.5, 2.0), 1e-12));
+        // the wall comes into range and the path bends around it
+        let (state, _) = planner.next_values(state, Some(at(0.6)));
+        let map = &state.map;
+        assert!((0..map.height).any(|j| map.is_occupied((19, j)) || map.is_occupied((20, j))));
+        assert!(state.waypoints.len() > 1);
+        assert!(planner.is_open(&state.map, Point::new(0.6, 2.0), &state.waypoints));
+    }
+}
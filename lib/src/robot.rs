@@ -0,0 +1,208 @@
+use std::{f64::consts::PI, str::FromStr, sync::LazyLock};
+
+use crate::{
+    io::{Point, Pose},
+    world::{Arg, ParseError, ParseErrorKind, parse_statements},
+};
+
+/// As many sonars as `SensorInput::sonars` has readings for.
+pub const MAX_SONARS: usize = 8;
+
+/// Sonar ring and body of a robot. Sonar poses are in the robot frame,
+/// listed left to right.
+#[derive(Debug, Clone)]
+pub struct RobotModel {
+    /// At most `MAX_SONARS` of them.
+    pub sonar_poses: Vec<Pose>,
+    /// Readings at or beyond this range mean the sonar saw nothing.
+    pub sonar_max: f64,
+    /// Radius of the circle enclosing the robot body.
+    pub radius: f64,
+    /// The (front, back) sonars used to measure the wall on the right.
+    pub right_sonars: (usize, usize),
+}
+
+const PIONEER_SONARS: [Pose; 8] = [
+    Pose {
+        pos: Point::new(0.08, 0.134),
+        theta: PI / 2.0,
+    },
+    Pose {
+        pos: Point::new(0.122, 0.118),
+        theta: 5.0 * PI / 18.0,
+    },
+    Pose {
+        pos: Point::new(0.156, 0.077),
+        theta: PI / 6.0,
+    },
+    Pose {
+        pos: Point::new(0.174, 0.0266),
+        theta: PI / 18.0,
+    },
+    Pose {
+        pos: Point::new(0.174, -0.0266),
+        theta: -PI / 18.0,
+    },
+    Pose {
+        pos: Point::new(0.156, -0.077),
+        theta: -PI / 6.0,
+    },
+    Pose {
+        pos: Point::new(0.122, -0.118),
+        theta: -5.0 * PI / 18.0,
+    },
+    Pose {
+        pos: Point::new(0.08, -0.134),
+        theta: -PI / 2.0,
+    },
+];
+
+pub(crate) static PIONEER: LazyLock<RobotModel> = LazyLock::new(RobotModel::pioneer);
+
+impl RobotModel {
+    /// The lab's Pioneer, whose eight front sonars fill `SensorInput`.
+    pub fn pioneer() -> Self {
+        Self {
+            sonar_poses: PIONEER_SONARS.to_vec(),
+            sonar_max: 1.5,
+            radius: 0.2,
+            right_sonars: (6, 7),
+        }
+    }
+
+    /// Reads a model from soar-style statements:
+    ///
+    /// ```text
+    /// radius(0.2)
+    /// sonarMax(1.5)
+    /// sonar((0.08, 0.134), 90)    # mounting point, heading in degrees
+    /// rightSonars(6, 7)
+    /// ```
+    ///
+    /// with one `sonar` statement per sonar, left to right, and at most
+    /// `MAX_SONARS` of them.
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut sonar_poses = Vec::new();
+        let mut sonar_max = None;
+        let mut radius = None;
+        let mut right_sonars = None;
+
+        for stmt in parse_statements(src)? {
+            match stmt.name.as_str() {
+                "sonar" if sonar_poses.len() == MAX_SONARS => {
+                    return Err(stmt.error(ParseErrorKind::Unexpected {
+                        expected: "at most 8 sonars",
+                        found: (MAX_SONARS + 1).to_string(),
+                    }));
+                }
+                "sonar" => match stmt.args[..] {
+                    [Arg::Point(pos), Arg::Number(degrees)] => sonar_poses.push(Pose {
+                        pos,
+                        theta: degrees.to_radians(),
+                    }),
+                    _ => {
+                        return Err(stmt.error(ParseErrorKind::Arity {
+                            statement: stmt.name.clone(),
+                            expected: "a point and a heading".to_string(),
+                            found: stmt.args.len(),
+                        }));
+                    }
+                },
+                "sonarMax" => {
+                    let [max] = stmt.numbers()?;
+                    sonar_max = Some(max);
+                }
+                "radius" => {
+                    let [r] = stmt.numbers()?;
+                    radius = Some(r);
+                }
+                "rightSonars" => {
+                    let [front, back] = stmt.numbers()?.map(|n| {
+                        (n.fract() == 0.0 && 0.0 <= n && n < sonar_poses.len() as f64)
+                            .then_some(n as usize)
+                            .ok_or(n)
+                    });
+                    match (front, back) {
+                        (Ok(front), Ok(back)) => right_sonars = Some((front, back)),
+                        (Err(n), _) | (_, Err(n)) => {
+                            return Err(stmt.error(ParseErrorKind::Unexpected {
+                                expected: "the index of a sonar listed above",
+                                found: n.to_string(),
+                            }));
+                        }
+                    }
+                }
+                _ => return Err(stmt.error(ParseErrorKind::UnknownStatement(stmt.name.clone()))),
+            }
+        }
+
+        let missing = |name| ParseError {
+            line: src.lines().count() + 1,
+            column: 1,
+            kind: ParseErrorKind::Missing(name),
+        };
+        if sonar_poses.is_empty() {
+            return Err(missing("sonar"));
+        }
+        Ok(Self {
+            sonar_poses,
+            sonar_max: sonar_max.ok_or(missing("sonarMax"))?,
+            radius: radius.ok_or(missing("radius"))?,
+            right_sonars: right_sonars.ok_or(missing("rightSonars"))?,
+        })
+    }
+}
+
+impl Default for RobotModel {
+    fn default() -> Self {
+        Self::pioneer()
+    }
+}
+
+impl FromStr for RobotModel {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIONEER_SRC: &str = include_str!("../../robots/pioneer.py");
+
+    #[test]
+    fn test_pioneer_file() {
+        let parsed = RobotModel::parse(PIONEER_SRC).unwrap();
+        let pioneer = RobotModel::default();
+        assert_eq!(parsed.sonar_poses.len(), pioneer.sonar_poses.len());
+        for (p, q) in parsed.sonar_poses.iter().zip(&pioneer.sonar_poses) {
+            assert!(p.pos.is_near(q.pos, 1e-12));
+            assert!((p.theta - q.theta).abs() < 1e-12);
+        }
+        assert_eq!(parsed.sonar_max, pioneer.sonar_max);
+        assert_eq!(parsed.radius, pioneer.radius);
+        assert_eq!(parsed.right_sonars, pioneer.right_sonars);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = RobotModel::parse("radius(0.2)\nsonar(90)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(matches!(err.kind, ParseErrorKind::Arity { found: 1, .. }));
+        let err = RobotModel::parse("sonar((0, 0), -90)\nrightSonars(0, 1)\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2:1: expected the index of a sonar listed above, found `1`"
+        );
+        let err = RobotModel::parse("rightSonars(0, 0)\nsonar((0, 0), -90)\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        let nine = "sonar((0, 0), 0)\n".repeat(9);
+        let err = RobotModel::parse(&nine).unwrap_err();
+        assert_eq!(err.to_string(), "9:1: expected at most 8 sonars, found `9`");
+        let err = RobotModel::parse("sonar((0, 0), -90)\nsonarMax(3)\nradius(0.1)\n").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Missing("rightSonars"));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use crate::{sm::StateMachine, trace::look_ahead};
+
+// Port of lib601's `search` module. Every search prunes states it has
+// already reached (lib601's dynamic programming) and gives up after
+// expanding `max_nodes` states.
+
+/// A sequence of actions from `start`, with the state each one leads to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<S, A> {
+    pub start: S,
+    pub steps: Vec<(A, S)>,
+    /// Sum of the step costs; the number of steps for unweighted searches.
+    pub cost: f64,
+}
+
+impl<S, A> Path<S, A> {
+    pub fn actions(&self) -> impl Iterator<Item = &A> {
+        self.steps.iter().map(|(action, _)| action)
+    }
+
+    /// Every state along the path, `start` included.
+    pub fn states(&self) -> impl Iterator<Item = &S> {
+        std::iter::once(&self.start).chain(self.steps.iter().map(|(_, state)| state))
+    }
+
+    pub fn end(&self) -> &S {
+        self.steps.last().map_or(&self.start, |(_, state)| state)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchStats {
+    /// States whose successors were generated.
+    pub expanded: usize,
+    /// Nodes added to the agenda, the start included.
+    pub generated: usize,
+    /// Largest size the agenda reached.
+    pub max_agenda: usize,
+}
+
+/// The path found, if any, and what it took to find it.
+#[derive(Debug, Clone)]
+pub struct SearchOutcome<S, A> {
+    pub path: Option<Path<S, A>>,
+    pub stats: SearchStats,
+}
+
+struct Node<S, A> {
+    state: S,
+    parent: Option<(usize, A)>,
+    cost: f64,
+}
+
+/// Walks parent links back from `node` to the start.
+fn path_to<S: Clone, A: Clone>(nodes: &[Node<S, A>], mut node: usize) -> Path<S, A> {
+    let cost = nodes[node].cost;
+    let mut steps = vec![];
+    while let Some((parent, action)) = &nodes[node].parent {
+        steps.push((action.clone(), nodes[node].state.clone()));
+        node = *parent;
+    }
+    steps.reverse();
+    Path {
+        start: nodes[node].state.clone(),
+        steps,
+        cost,
+    }
+}
+
+/// Fewest-steps path from `start` to a state satisfying `goal`.
+pub fn breadth_first<S, A, I>(
+    start: S,
+    goal: impl Fn(&S) -> bool,
+    successors: impl Fn(&S) -> I,
+    max_nodes: usize,
+) -> SearchOutcome<S, A>
+where
+    S: Clone + Eq + Hash,
+    A: Clone,
+    I: IntoIterator<Item = (A, S)>,
+{
+    blind_search(start, goal, successors, max_nodes, false)
+}
+
+/// Some path from `start` to a state satisfying `goal`, exploring the most
+/// recently reached states first.
+pub fn depth_first<S, A, I>(
+    start: S,
+    goal: impl Fn(&S) -> bool,
+    successors: impl Fn(&S) -> I,
+    max_nodes: usize,
+) -> SearchOutcome<S, A>
+where
+    S: Clone + Eq + Hash,
+    A: Clone,
+    I: IntoIterator<Item = (A, S)>,
+{
+    blind_search(start, goal, successors, max_nodes, true)
+}
+
+fn blind_search<S, A, I>(
+    start: S,
+    goal: impl Fn(&S) -> bool,
+    successors: impl Fn(&S) -> I,
+    max_nodes: usize,
+    depth_first: bool,
+) -> SearchOutcome<S, A>
+where
+    S: Clone + Eq + Hash,
+    A: Clone,
+    I: IntoIterator<Item = (A, S)>,
+{
+    let mut stats = SearchStats {
+        generated: 1,
+        max_agenda: 1,
+        ..Default::default()
+    };
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        parent: None,
+        cost: 0.0,
+    }];
+    if goal(&start) {
+        let path = Some(path_to(&nodes, 0));
+        return SearchOutcome { path, stats };
+    }
+    let mut visited = HashSet::from([start]);
+    let mut agenda = VecDeque::from([0]);
+    while stats.expanded < max_nodes {
+        let next = if depth_first {
+            agenda.pop_back()
+        } else {
+            agenda.pop_front()
+        };
+        let Some(parent) = next else {
+            break;
+        };
+        stats.expanded += 1;
+        for (action, state) in successors(&nodes[parent].state) {
+            if !visited.insert(state.clone()) {
+                continue;
+            }
+            let reached = goal(&state);
+            nodes.push(Node {
+                state,
+                parent: Some((parent, action)),
+                cost: nodes[parent].cost + 1.0,
+            });
+            stats.generated += 1;
+            if reached {
+                let path = Some(path_to(&nodes, nodes.len() - 1));
+                return SearchOutcome { path, stats };
+            }
+            agenda.push_back(nodes.len() - 1);
+        }
+        stats.max_agenda = stats.max_agenda.max(agenda.len());
+    }
+    SearchOutcome { path: None, stats }
+}
+
+/// Cheapest path from `start` to a state satisfying `goal`, where
+/// `successors` also gives the cost of each step.
+pub fn uniform_cost<S, A, I>(
+    start: S,
+    goal: impl Fn(&S) -> bool,
+    successors: impl Fn(&S) -> I,
+    max_nodes: usize,
+) -> SearchOutcome<S, A>
+where
+    S: Clone + Eq + Hash,
+    A: Clone,
+    I: IntoIterator<Item = (A, S, f64)>,
+{
+    a_star(start, goal, successors, |_| 0.0, max_nodes)
+}
+
+/// `uniform_cost` guided by `heuristic`, an estimate of the cost left to a
+/// goal. The path is the cheapest as long as the heuristic never
+/// overestimates and never drops by more than a step's cost along a step.
+pub fn a_star<S, A, I>(
+    start: S,
+    goal: impl Fn(&S) -> bool,
+    successors: impl Fn(&S) -> I,
+    heuristic: impl Fn(&S) -> f64,
+    max_nodes: usize,
+) -> SearchOutcome<S, A>
+where
+    S: Clone + Eq + Hash,
+    A: Clone,
+    I: IntoIterator<Item = (A, S, f64)>,
+{
+    let mut stats = SearchStats {
+        generated: 1,
+        max_agenda: 1,
+        ..Default::default()
+    };
+    let mut agenda = BinaryHeap::from([Entry {
+        priority: heuristic(&start),
+        node: 0,
+    }]);
+    let mut nodes = vec![Node {
+        state: start,
+        parent: None,
+        cost: 0.0,
+    }];
+    let mut expanded = HashSet::new();
+    while stats.expanded < max_nodes {
+        let Some(Entry { node: parent, .. }) = agenda.pop() else {
+            break;
+        };
+        if goal(&nodes[parent].state) {
+            let path = Some(path_to(&nodes, parent));
+            return SearchOutcome { path, stats };
+        }
+        if !expanded.insert(nodes[parent].state.clone()) {
+            continue;
+        }
+        stats.expanded += 1;
+        for (action, state, cost) in successors(&nodes[parent].state) {
+            if expanded.contains(&state) {
+                continue;
+            }
+            let cost = nodes[parent].cost + cost;
+            agenda.push(Entry {
+                priority: cost + heuristic(&state),
+                node: nodes.len(),
+            });
+            nodes.push(Node {
+                state,
+                parent: Some((parent, action)),
+                cost,
+            });
+            stats.generated += 1;
+        }
+        stats.max_agenda = stats.max_agenda.max(agenda.len());
+    }
+    SearchOutcome { path: None, stats }
+}
+
+/// Agenda entry ordered so that `BinaryHeap` pops the lowest priority
+/// first, and the oldest node among equals.
+struct Entry {
+    priority: f64,
+    node: usize,
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+/// lib601's `smSearch`: breadth-first search for the shortest sequence of
+/// `legal_inputs` driving `machine` from its start state to one satisfying
+/// `goal`, using `next_values` as the transition model.
+pub fn sm_search<In, Out, SM>(
+    machine: &SM,
+    legal_inputs: &[In],
+    goal: impl Fn(&SM::State) -> bool,
+    max_nodes: usize,
+) -> SearchOutcome<SM::State, In>
+where
+    In: Clone,
+    SM: StateMachine<In, Out>,
+    SM::State: Eq + Hash,
+{
+    breadth_first(
+        machine.start_state(),
+        goal,
+        |state| {
+            legal_inputs
+                .iter()
+                .map(|input| {
+                    let (next, _) =
+                        look_ahead(|| machine.next_values(state.clone(), Some(input.clone())));
+                    (input.clone(), next)
+                })
+                .collect::<Vec<_>>()
+        },
+        max_nodes,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  a -1- b -1- c
+    //  |           |
+    //  5           1
+    //  |           |
+    //  d ----1---- e -1- f
+    fn graph(node: &char) -> Vec<(char, char, f64)> {
+        let edges = [
+            ('a', 'b', 1.0),
+            ('b', 'c', 1.0),
+            ('a', 'd', 5.0),
+            ('c', 'e', 1.0),
+            ('d', 'e', 1.0),
+            ('e', 'f', 1.0),
+        ];
+        edges
+            .iter()
+            .filter_map(|&(u, v, cost)| match *node {
+                n if n == u => Some((v, v, cost)),
+                n if n == v => Some((u, u, cost)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn unweighted(node: &char) -> Vec<(char, char)> {
+        graph(node).into_iter().map(|(a, s, _)| (a, s)).collect()
+    }
+
+    #[test]
+    fn test_breadth_first() {
+        let outcome = breadth_first('a', |&s| s == 'e', unweighted, 100);
+        let path = outcome.path.unwrap();
+        assert_eq!(path.states().collect::<String>(), "ade");
+        assert_eq!(path.cost, 2.0);
+        assert_eq!(*path.end(), 'e');
+        assert!(outcome.stats.expanded <= 4);
+
+        let start = breadth_first('f', |&s| s == 'f', unweighted, 100);
+        assert_eq!(start.path.unwrap().steps, vec![]);
+    }
+
+    #[test]
+    fn test_depth_first() {
+        let path = depth_first('a', |&s| s == 'f', unweighted, 100)
+            .path
+            .unwrap();
+        assert_eq!(path.states().next(), Some(&'a'));
+        assert_eq!(*path.end(), 'f');
+        let states = path.states().collect::<HashSet<_>>();
+        assert_eq!(states.len(), path.steps.len() + 1, "revisits a state");
+    }
+
+    #[test]
+    fn test_uniform_cost_prefers_cheap_path() {
+        let path = uniform_cost('a', |&s| s == 'd', graph, 100).path.unwrap();
+        assert_eq!(path.actions().collect::<String>(), "bced");
+        assert_eq!(path.cost, 4.0);
+    }
+
+    #[test]
+    fn test_a_star_on_grid() {
+        type P = (i32, i32);
+        let wall = |(x, y): P| x == 5 && y < 8;
+        let moves = |&(x, y): &P| {
+            [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .map(move |(dx, dy)| ((dx, dy), (x + dx, y + dy), 1.0))
+                .filter(move |&(_, p, _)| {
+                    (0..10).contains(&p.0) && (0..10).contains(&p.1) && !wall(p)
+                })
+        };
+        let goal = (9, 0);
+        let manhattan = |&(x, y): &P| ((goal.0 - x).abs() + (goal.1 - y).abs()) as f64;
+        let ucs = uniform_cost((0, 0), |&p| p == goal, moves, 1000);
+        let astar = a_star((0, 0), |&p| p == goal, moves, manhattan, 1000);
+        let (ucs_path, astar_path) = (ucs.path.unwrap(), astar.path.unwrap());
+        assert_eq!(ucs_path.cost, 25.0);
+        assert_eq!(astar_path.cost, ucs_path.cost);
+        assert!(astar_path.states().all(|&p| !wall(p)));
+        assert!(astar.stats.expanded < ucs.stats.expanded);
+
+        let capped = a_star((0, 0), |&p| p == goal, moves, manhattan, 10);
+        assert!(capped.path.is_none());
+        assert_eq!(capped.stats.expanded, 10);
+    }
+
+    #[test]
+    fn test_sm_search() {
+        // lib601's number test: reach 10 from 1 with x + 1, x * 2 and x - 3
+        let machine = (
+            |state: i32, op: char| {
+                let next = match op {
+                    '+' => state + 1,
+                    '*' => state * 2,
+                    _ => state - 3,
+                };
+                (next, next)
+            },
+            1,
+        );
+        let outcome = sm_search(&machine, &['+', '*', '-'], |&s| s == 10, 1000);
+        let path = outcome.path.unwrap();
+        assert_eq!(path.steps.len(), 4);
+        assert_eq!(
+            path.states().copied().collect::<Vec<_>>(),
+            vec![1, 2, 4, 5, 10]
+        );
+        assert!(outcome.stats.generated > path.steps.len());
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
-    poly::{DispPoly, Poly},
+    bode::FrequencyResponse,
+    complex::Complex,
+    poly::{DispPoly, Poly, PolyError},
     sm::StateMachine,
 };
-#[cfg(feature = "poles")]
-use faer::complex::Complex;
 
-use std::{fmt::Display, ops::Deref};
+use std::{f64::consts::PI, fmt::Display, iter, ops::Deref};
 
 #[derive(Debug)]
 pub struct Poles(Vec<Pole>);
@@ -52,11 +52,48 @@ impl Pole {
             Pole::Complex(re, im) => (re.powi(2) + im.powi(2)).sqrt(),
         }
     }
+
+    /// Period of the oscillation this pole causes, with samples `t`
+    /// seconds apart; `None` for a positive real pole.
+    pub fn period(&self, t: f64) -> Option<f64> {
+        let angle = match *self {
+            Pole::Real(r) if r < 0.0 => PI,
+            Pole::Real(_) => return None,
+            Pole::Complex(re, im) => im.atan2(re).abs(),
+        };
+        Some(2.0 * PI * t / angle)
+    }
+
+    /// Time for the mode of this pole to shrink by a factor e, with samples
+    /// `t` seconds apart; `None` if it does not decay.
+    pub fn decay_time(&self, t: f64) -> Option<f64> {
+        let m = self.magnitude();
+        (m < 1.0).then(|| -t / m.ln())
+    }
+}
+
+/// Figures of the response to a unit step, in steps: multiply by the
+/// sample period for seconds.
+#[derive(Debug, Clone)]
+pub struct StepMetrics {
+    /// The DC gain, or the last sample when the gain is infinite.
+    pub final_value: f64,
+    /// From reaching 10% of the final value to reaching 90% of it.
+    pub rise_time: Option<usize>,
+    /// Until the output stays within the tolerance band; `None` if it is
+    /// still outside at the last sample or the gain is infinite.
+    pub settling_time: Option<usize>,
+    /// Peak past the final value, in percent of it.
+    pub overshoot: f64,
+    /// `1 - final_value`, how far the output stays from the input.
+    pub steady_state_error: f64,
+    /// Between crossings of the final value in the same direction, when it
+    /// is crossed at least 3 times.
+    pub period: Option<f64>,
 }
 
-#[cfg(feature = "poles")]
-impl From<Complex<f64>> for Pole {
-    fn from(value: Complex<f64>) -> Self {
+impl From<Complex> for Pole {
+    fn from(value: Complex) -> Self {
         if value.im == 0.0 {
             Pole::Real(value.re)
         } else {
@@ -65,6 +102,32 @@ impl From<Complex<f64>> for Pole {
     }
 }
 
+/// Why a system function could not be built or analysed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SfError {
+    Poly(PolyError),
+    /// The loop cancels the denominator out to zero, e.g.
+    /// `gain(1.0).feedback_add(None)`.
+    DegenerateFeedback,
+}
+
+impl Display for SfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SfError::Poly(err) => write!(f, "{err}"),
+            SfError::DegenerateFeedback => write!(f, "feedback loop has a zero denominator"),
+        }
+    }
+}
+
+impl std::error::Error for SfError {}
+
+impl From<PolyError> for SfError {
+    fn from(value: PolyError) -> Self {
+        SfError::Poly(value)
+    }
+}
+
 pub struct SystemFunction {
     numerator: Poly,
     denominator: Poly,
@@ -85,52 +148,71 @@ impl SystemFunction {
         }
     }
 
+    #[track_caller]
     pub fn feedback_sub(self, other: Option<SystemFunction>) -> Self {
+        self.try_feedback_sub(other)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[track_caller]
+    pub fn feedback_add(self, other: Option<SystemFunction>) -> Self {
+        self.try_feedback_add(other)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[track_caller]
+    pub fn feedforward_add(self, other: Option<SystemFunction>) -> Self {
+        self.try_feedforward_add(other)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// `self` with `other`, or a wire, subtracted back into its input.
+    pub fn try_feedback_sub(self, other: Option<SystemFunction>) -> Result<Self, SfError> {
         let SystemFunction {
             numerator: n1,
             denominator: d1,
         } = self;
+        let (n2, d2) = Self::or_wire(other);
 
-        let (n2, d2) = match other {
-            Some(other) => (other.numerator, other.denominator),
-            None => (Poly::new([1.0]), Poly::new([1.0])),
-        };
-
-        Self {
+        Ok(Self {
             numerator: n1.clone() * d2.clone(),
-            denominator: (d1 * d2) + (n1 * n2),
-        }
+            denominator: ((d1 * d2).try_add(n1 * n2)).map_err(|_| SfError::DegenerateFeedback)?,
+        })
     }
 
-    pub fn feedback_add(self, other: Option<SystemFunction>) -> Self {
+    /// `self` with `other`, or a wire, added back into its input.
+    pub fn try_feedback_add(self, other: Option<SystemFunction>) -> Result<Self, SfError> {
         let SystemFunction {
             numerator: n1,
             denominator: d1,
         } = self;
-        let (n2, d2) = match other {
-            Some(other) => (other.numerator, other.denominator),
-            None => (Poly::new([1.0]), Poly::new([1.0])),
-        };
+        let (n2, d2) = Self::or_wire(other);
 
-        Self {
+        Ok(Self {
             numerator: n1.clone() * d2.clone(),
-            denominator: (d1 * d2) - (n1 * n2),
-        }
+            denominator: ((d1 * d2).try_sub(n1 * n2)).map_err(|_| SfError::DegenerateFeedback)?,
+        })
     }
 
-    pub fn feedforward_add(self, other: Option<SystemFunction>) -> Self {
+    /// The sum of `self` and `other`, or a wire, fed the same input; fails
+    /// when they cancel out.
+    pub fn try_feedforward_add(self, other: Option<SystemFunction>) -> Result<Self, SfError> {
         let SystemFunction {
             numerator: n1,
             denominator: d1,
         } = self;
-        let (n2, d2) = match other {
-            Some(other) => (other.numerator, other.denominator),
-            None => (Poly::new([1.0]), Poly::new([1.0])),
-        };
+        let (n2, d2) = Self::or_wire(other);
 
-        Self {
-            numerator: n1 * d2.clone() + n2 * d1.clone(),
+        Ok(Self {
+            numerator: (n1 * d2.clone()).try_add(n2 * d1.clone())?,
             denominator: d1 * d2,
+        })
+    }
+
+    fn or_wire(other: Option<SystemFunction>) -> (Poly, Poly) {
+        match other {
+            Some(other) => (other.numerator, other.denominator),
+            None => (Poly::new([1.0]), Poly::new([1.0])),
         }
     }
 }
@@ -146,16 +228,181 @@ impl Display for SystemFunction {
     }
 }
 impl SystemFunction {
-    #[cfg(feature = "poles")]
+    #[track_caller]
     pub fn poles(&self) -> Poles {
+        self.try_poles().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn try_poles(&self) -> Result<Poles, SfError> {
         let p = self.denominator.reciprocal();
         // eprintln!("reci {}", DispPoly::<'z'>(&p));
-        p.roots()
+        Ok(p.try_roots()?
             .into_iter()
             .map(Pole::from)
             .collect::<Vec<_>>()
-            .into()
+            .into())
+    }
+
+    #[track_caller]
+    pub fn zeros(&self) -> Vec<Pole> {
+        self.try_zeros().unwrap_or_else(|err| panic!("{err}"))
     }
+
+    /// The roots of the numerator in `z`, found like the poles.
+    pub fn try_zeros(&self) -> Result<Vec<Pole>, SfError> {
+        let p = self.numerator.reciprocal();
+        Ok(p.try_roots()?.into_iter().map(Pole::from).collect())
+    }
+
+    /// The gain and phase shift applied to a sinusoid of angular frequency
+    /// `omega`, in radians per step: the value at `R = e^{-j omega}`.
+    pub fn response(&self, omega: f64) -> Complex {
+        let r = Complex::from_polar(1.0, -omega);
+        self.numerator.eval_complex(r) / self.denominator.eval_complex(r)
+    }
+
+    /// Samples `response` at `omegas`, in increasing order.
+    pub fn frequency_response(&self, omegas: impl IntoIterator<Item = f64>) -> FrequencyResponse {
+        FrequencyResponse::new(
+            omegas
+                .into_iter()
+                .map(|omega| (omega, self.response(omega))),
+        )
+    }
+
+    /// The first `steps` outputs for a unit step input from rest; `None`
+    /// when the denominator has no constant term, as the output then
+    /// depends on future ones.
+    pub fn step_response(&self, steps: usize) -> Option<Vec<f64>> {
+        // LTSIM takes the denominator's constant term to be 1
+        let constant = self.denominator.eval(0.0);
+        if constant == 0.0 {
+            return None;
+        }
+        let scale = Poly::new([1.0 / constant]);
+        let sf = SystemFunction::new(
+            self.numerator.clone() * scale.clone(),
+            self.denominator.clone() * scale,
+        );
+        Some(
+            LTSIM::from_sf(sf, None, None)
+                .transduce(iter::repeat_n(1.0, steps))
+                .collect(),
+        )
+    }
+
+    /// Measures `step_response(steps)`, with a settling band of
+    /// `tolerance` times the final value. `None` when there is no step
+    /// response, or when the final value is 0 or not finite, as there is
+    /// nothing to measure against.
+    pub fn step_metrics(&self, steps: usize, tolerance: f64) -> Option<StepMetrics> {
+        let samples = self.step_response(steps)?;
+        let gain = self.dc_gain();
+        let final_value = if gain.is_finite() {
+            gain
+        } else {
+            samples.last().copied().unwrap_or(0.0)
+        };
+        if final_value == 0.0 || !final_value.is_finite() {
+            return None;
+        }
+        let normalized = samples.iter().map(|y| y / final_value).collect::<Vec<_>>();
+
+        let reach = |level| normalized.iter().position(|&y| y >= level);
+        let rise_time = reach(0.9).zip(reach(0.1)).map(|(high, low)| high - low);
+        let settling_time = match normalized.iter().rposition(|y| (y - 1.0).abs() > tolerance) {
+            _ if !gain.is_finite() => None,
+            Some(k) if k + 1 == normalized.len() => None,
+            Some(k) => Some(k + 1),
+            None => Some(0),
+        };
+        let peak = normalized.iter().copied().fold(1.0, f64::max);
+        let crossings = normalized
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| (pair[0] - 1.0) * (pair[1] - 1.0) < 0.0)
+            .map(|(k, pair)| k as f64 + (1.0 - pair[0]) / (pair[1] - pair[0]))
+            .collect::<Vec<_>>();
+        let period = match crossings[..] {
+            [first, .., last] if crossings.len() >= 3 => {
+                Some(2.0 * (last - first) / (crossings.len() - 1) as f64)
+            }
+            _ => None,
+        };
+
+        Some(StepMetrics {
+            final_value,
+            rise_time,
+            settling_time,
+            overshoot: (peak - 1.0) * 100.0,
+            steady_state_error: 1.0 - final_value,
+            period,
+        })
+    }
+
+    /// The output a unit step input settles to, that is the value at
+    /// `z = 1`; infinite when there is a pole at 1.
+    pub fn dc_gain(&self) -> f64 {
+        self.numerator.eval(1.0) / self.denominator.eval(1.0)
+    }
+
+    #[track_caller]
+    pub fn simplify(self, tolerance: f64) -> Self {
+        self.try_simplify(tolerance)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Cancels the delays common to both sides and each zero lying within
+    /// `tolerance` of a pole, then scales the denominator's lowest-order
+    /// coefficient to 1, as `into_sm` expects. Real zeros only cancel real
+    /// poles, and complex ones only together with their conjugates, so the
+    /// coefficients stay real.
+    pub fn try_simplify(self, tolerance: f64) -> Result<Self, SfError> {
+        let (n_gain, n_delays, zeros) = factor(&self.numerator)?;
+        let (d_gain, d_delays, poles) = factor(&self.denominator)?;
+        // roots off the real axis by rounding errors only count as real
+        let snap = |roots: Vec<Complex>| {
+            roots
+                .into_iter()
+                .map(|z| {
+                    if z.im.abs() <= 1e-6 * z.norm().max(1.0) {
+                        Complex::from(z.re)
+                    } else {
+                        z
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let (mut zeros, mut poles) = (snap(zeros), snap(poles));
+        let mut kept = Vec::with_capacity(zeros.len());
+        while let Some(zero) = zeros.pop() {
+            let Some(pole) = nearest(&poles, zero, tolerance) else {
+                kept.push(zero);
+                continue;
+            };
+            if zero.im == 0.0 {
+                poles.swap_remove(pole);
+                continue;
+            }
+            let conj_zero = nearest(&zeros, zero.conj(), tolerance);
+            let conj_pole = nearest(&poles, poles[pole].conj(), tolerance).filter(|&k| k != pole);
+            match (conj_zero, conj_pole) {
+                (Some(conj_zero), Some(conj_pole)) => {
+                    zeros.swap_remove(conj_zero);
+                    poles.swap_remove(pole.max(conj_pole));
+                    poles.swap_remove(pole.min(conj_pole));
+                }
+                _ => kept.push(zero),
+            }
+        }
+        let zeros = kept;
+        let delays = n_delays.min(d_delays);
+        Ok(Self {
+            numerator: unfactor(n_gain / d_gain, n_delays - delays, &zeros),
+            denominator: unfactor(1.0, d_delays - delays, &poles),
+        })
+    }
+
     pub fn into_sm(
         self,
         prev_inputs: Option<Vec<f64>>,
@@ -165,11 +412,59 @@ impl SystemFunction {
     }
 }
 
+/// `p` as `gain * R^delays * (1 - z1 R) * (1 - z2 R) ...` over its roots
+/// `zk` in `z`.
+fn factor(p: &Poly) -> Result<(f64, usize, Vec<Complex>), SfError> {
+    let coeffs = p.clone().coeffs();
+    let delays = coeffs.iter().rev().take_while(|c| **c == 0.0).count();
+    let gain = coeffs[coeffs.len() - 1 - delays];
+    Ok((gain, delays, p.reciprocal().try_roots()?))
+}
+
+/// The root of the same kind, real or complex, as `to` nearest to it, if
+/// within `tolerance`.
+fn nearest(roots: &[Complex], to: Complex, tolerance: f64) -> Option<usize> {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| (root.im == 0.0) == (to.im == 0.0))
+        .map(|(k, &root)| (k, (root - to).norm()))
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .filter(|(_, d)| *d <= tolerance)
+        .map(|(k, _)| k)
+}
+
+fn unfactor(gain: f64, delays: usize, roots: &[Complex]) -> Poly {
+    // lowest order first
+    let mut coeffs = vec![Complex::from(gain)];
+    for &z in roots {
+        coeffs.push(Complex::default());
+        for k in (1..coeffs.len()).rev() {
+            coeffs[k] = coeffs[k] - z * coeffs[k - 1];
+        }
+    }
+    // conjugate pairs leave only rounding errors in the imaginary parts
+    debug_assert!(
+        coeffs
+            .iter()
+            .all(|c| c.im.abs() <= 1e-6 * c.norm().max(1.0)),
+        "roots without their conjugates: {roots:?}"
+    );
+    let coeffs = coeffs.iter().rev().map(|c| c.re);
+    Poly::from_vec(coeffs.chain(iter::repeat_n(0.0, delays)).collect())
+}
+
+#[track_caller]
 pub fn gain(k: f64) -> SystemFunction {
-    SystemFunction {
-        numerator: Poly::new([k]),
+    try_gain(k).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Fails for a gain of 0, which has a zero numerator.
+pub fn try_gain(k: f64) -> Result<SystemFunction, SfError> {
+    Ok(SystemFunction {
+        numerator: Poly::try_new([k])?,
         denominator: Poly::new([1.0]),
-    }
+    })
 }
 
 pub fn delay() -> SystemFunction {
@@ -212,8 +507,6 @@ impl LTSIM {
                 v
             })
             .unwrap_or_else(|| vec![0.0; k]);
-        dbg!(&c_coeffs);
-        dbg!(&d_coeffs);
         Self {
             c_coeffs,
             d_coeffs,
@@ -256,14 +549,22 @@ impl StateMachine<f64, f64> for LTSIM {
         (self.prev_inputs.clone(), self.prev_outputs.clone())
     }
 
+    fn validate(&self, (inputs, outputs): &Self::State) -> bool {
+        let (start_inputs, start_outputs) = self.start_state();
+        inputs.len() == start_inputs.len() && outputs.len() == start_outputs.len()
+    }
+
     fn next_values(
         &self,
         (mut inputs, mut outputs): Self::State,
         input: Option<f64>,
     ) -> (Self::State, Option<f64>) {
+        let Some(input) = input else {
+            return ((inputs, outputs), None);
+        };
         let inputs_len = inputs.len();
         inputs.rotate_left(inputs_len - 1);
-        unsafe { *inputs.get_unchecked_mut(0) = input.expect("no input") };
+        unsafe { *inputs.get_unchecked_mut(0) = input };
         let mut output = dot_product(&outputs, &self.c_coeffs);
         output += dot_product(&inputs, &self.d_coeffs);
 
@@ -277,11 +578,12 @@ impl StateMachine<f64, f64> for LTSIM {
 
 #[cfg(test)]
 mod tests {
-    use std::cmp::Ordering;
+    use std::{cmp::Ordering, f64::consts::PI};
 
     use crate::{
-        poly::Poly,
-        sf::{LTSIM, Pole, Poles, SystemFunction},
+        poly::{Poly, PolyError},
+        sf::{LTSIM, Pole, Poles, SfError, SystemFunction, delay, gain, try_gain},
+        sm::StateMachine,
     };
 
     impl Pole {
@@ -389,6 +691,142 @@ right: {right_val:?}"#
         pole_assert_eq(Pole::Real(0.8), poles[0])
     }
 
+    #[test]
+    fn test_zeros_and_gain() {
+        // (1 - 0.5R) / ((1 - 0.5R)(1 - 0.8R))
+        let s = SystemFunction::new(Poly::new([-0.5, 1.0]), Poly::new([0.4, -1.3, 1.0]));
+        let zeros = s.zeros();
+        assert_eq!(zeros.len(), 1);
+        pole_assert_eq(Pole::Real(0.5), zeros[0]);
+        float_assert_eq(5.0, s.dc_gain());
+        assert_eq!(delay().dc_gain(), 1.0);
+        float_assert_eq(1.0 / 3.0, gain(0.5).feedback_sub(None).dc_gain());
+        float_assert_eq(1.0, gain(0.5).feedback_add(None).dc_gain());
+        assert!(
+            gain(1.0)
+                .feedback_add(Some(delay()))
+                .dc_gain()
+                .is_infinite()
+        );
+    }
+
+    #[test]
+    fn test_simplify() {
+        // 2R^2 (1 - 0.5R) / (4R (1 - 0.5R)(1 - 0.8R))
+        let s = SystemFunction::new(
+            Poly::new([-1.0, 2.0, 0.0, 0.0]),
+            Poly::new([1.6, -5.2, 4.0, 0.0]),
+        );
+        let s = s.simplify(1e-9);
+        vec_float_assert_eq(&s.numerator.clone().coeffs(), &[0.5, 0.0]);
+        vec_float_assert_eq(&s.denominator.clone().coeffs(), &[-0.8, 1.0]);
+        assert_eq!(s.to_string(), "SF(0.5R / -0.8R + 1)");
+
+        // conjugate poles stay, cancelled or not
+        let resonant = SystemFunction::new(Poly::new([1.0]), Poly::new([0.81, 0.0, 1.0]));
+        let zeroed = resonant.cascade(SystemFunction::new(
+            Poly::new([0.81, 0.0, 1.0]),
+            Poly::new([-0.5, 1.0]),
+        ));
+        let s = zeroed.simplify(1e-9);
+        vec_float_assert_eq(&s.numerator.clone().coeffs(), &[1.0]);
+        vec_float_assert_eq(&s.denominator.clone().coeffs(), &[-0.5, 1.0]);
+        let s = SystemFunction::new(Poly::new([1.0]), Poly::new([0.81, 0.0, 2.0])).simplify(1e-9);
+        vec_float_assert_eq(&s.denominator.clone().coeffs(), &[0.405, 0.0, 1.0]);
+
+        // complex zeros 0.5 ± 0.04j within tolerance of a real pole stay
+        let s = SystemFunction::new(Poly::new([0.2516, -1.0, 1.0]), Poly::new([-0.5, 1.0]));
+        let s = s.simplify(0.05);
+        vec_float_assert_eq(&s.numerator.clone().coeffs(), &[0.2516, -1.0, 1.0]);
+        vec_float_assert_eq(&s.denominator.clone().coeffs(), &[-0.5, 1.0]);
+
+        // the minimal machine behaves like the original
+        let original = || SystemFunction::new(Poly::new([-0.5, 1.0]), Poly::new([0.4, -1.3, 1.0]));
+        let inputs = [1.0, 0.0, 2.0, -1.0, 0.5, 0.0, 0.0];
+        let expected = original()
+            .into_sm(None, None)
+            .transduce(inputs)
+            .collect::<Vec<_>>();
+        let actual = original()
+            .simplify(1e-9)
+            .into_sm(None, None)
+            .transduce(inputs)
+            .collect::<Vec<_>>();
+        vec_float_assert_eq(&actual, &expected);
+    }
+
+    #[test]
+    fn test_step_metrics() {
+        // y[n] = 0.5 + 0.5 y[n - 1] = 1 - 0.5^(n + 1)
+        let lowpass = SystemFunction::new(Poly::new([0.5]), Poly::new([-0.5, 1.0]));
+        let metrics = lowpass.step_metrics(50, 0.02).unwrap();
+        assert_eq!(metrics.final_value, 1.0);
+        assert_eq!(metrics.rise_time, Some(3));
+        assert_eq!(metrics.settling_time, Some(5));
+        assert_eq!(metrics.overshoot, 0.0);
+        assert_eq!(metrics.steady_state_error, 0.0);
+        assert_eq!(metrics.period, None);
+        assert_eq!(lowpass.step_metrics(3, 0.02).unwrap().settling_time, None);
+
+        // poles 0.9 e^{±0.5j} with the gain scaled to 1 at DC, given
+        // unnormalized
+        let a = 2.0 * 0.9 * 0.5f64.cos();
+        let dc = 1.0 - a + 0.81;
+        let s = SystemFunction::new(Poly::new([2.0 * dc]), Poly::new([1.62, -2.0 * a, 2.0]));
+        let metrics = s.step_metrics(300, 0.02).unwrap();
+        float_assert_eq(1.0, metrics.final_value);
+        assert!(metrics.overshoot > 10.0, "{metrics:?}");
+        let period = metrics.period.unwrap();
+        assert!((period - 4.0 * PI).abs() < 0.2, "{period}");
+        let dominant = s.poles().dominant();
+        float_assert_eq(0.4 * PI, dominant.period(0.1).unwrap());
+        float_assert_eq(-0.1 / 0.9f64.ln(), dominant.decay_time(0.1).unwrap());
+        assert_eq!(Pole::Real(0.5).period(0.1), None);
+        float_assert_eq(0.2, Pole::Real(-0.5).period(0.1).unwrap());
+        assert_eq!(Pole::Real(1.2).decay_time(0.1), None);
+
+        // an integrator keeps growing: its gain is measured off the last sample
+        let ramp = gain(1.0).feedback_add(Some(delay())).step_metrics(10, 0.02);
+        let ramp = ramp.unwrap();
+        assert_eq!(ramp.final_value, 10.0);
+        assert_eq!(ramp.settling_time, None);
+
+        // nothing to measure against when the output settles to 0 or is NaN
+        let difference = SystemFunction::new(Poly::new([-1.0, 1.0]), Poly::new([-0.5, 1.0]));
+        assert_eq!(difference.dc_gain(), 0.0);
+        assert!(difference.step_metrics(10, 0.02).is_none());
+        let nan = SystemFunction::new(Poly::new([f64::NAN]), Poly::new([-0.5, 1.0]));
+        assert!(nan.step_metrics(10, 0.02).is_none());
+
+        // y[n - 1] = x[n] has no step response to measure
+        let advance = SystemFunction::new(Poly::new([1.0]), Poly::new([1.0, 0.0]));
+        assert_eq!(advance.step_response(10), None);
+        assert!(advance.step_metrics(10, 0.02).is_none());
+    }
+
+    #[test]
+    fn test_degenerate() {
+        assert!(matches!(
+            gain(1.0).try_feedback_add(None),
+            Err(SfError::DegenerateFeedback)
+        ));
+        assert!(matches!(
+            gain(-1.0).try_feedback_sub(None),
+            Err(SfError::DegenerateFeedback)
+        ));
+        assert!(matches!(
+            gain(2.0).try_feedforward_add(Some(gain(-2.0))),
+            Err(SfError::Poly(PolyError::Zero))
+        ));
+        let nan = SystemFunction::new(Poly::new([1.0]), Poly::new([f64::NAN, 1.0]));
+        assert!(matches!(
+            nan.try_poles(),
+            Err(SfError::Poly(PolyError::Roots))
+        ));
+        assert!(gain(0.5).try_feedback_add(None).is_ok());
+        assert!(matches!(try_gain(0.0), Err(SfError::Poly(PolyError::Zero))));
+    }
+
     const EPSILON: f64 = 1e-10;
 
     fn assert_vec_approx_eq(actual: &[f64], expected: &[f64], msg: &str) {
@@ -427,6 +865,15 @@ right: {right_val:?}"#
         assert_vec_approx_eq(&ltsim.d_coeffs, &expected_d, "dCoeffs");
     }
 
+    #[test]
+    fn test_undefined_input() {
+        let ltsim = create_ltsim(vec![0.5, 0.5], vec![1.0, -0.5]);
+        let state = ltsim.start_state();
+        let (new_state, output) = ltsim.next_values(state.clone(), None);
+        assert_eq!(output, None);
+        assert_eq!(new_state, state);
+    }
+
     #[test]
     fn test_simple_lowpass() {
         let numerator = vec![0.5, 0.5];
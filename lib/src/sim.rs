@@ -0,0 +1,221 @@
+use crate::{
+    T,
+    io::{Action, Angle, Point, Pose, SensorInput},
+    robot::{MAX_SONARS, RobotModel},
+    sm::StateFullMachine,
+    world::World,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Wall {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Wall {
+    pub const fn new(start: Point, end: Point) -> Self {
+        Self { start, end }
+    }
+
+    /// Distance along the ray `origin + t * dir` (with `t >= 0`) at which it
+    /// crosses this wall, if it does.
+    fn cast(&self, origin: Point, dir: Point) -> Option<f64> {
+        let edge = self.end - self.start;
+        let denom = cross(dir, edge);
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+        let to_start = self.start - origin;
+        let t = cross(to_start, edge) / denom;
+        let s = cross(to_start, dir) / denom;
+        (t >= 0.0 && (0.0..=1.0).contains(&s)).then_some(t)
+    }
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Headless unicycle robot stepped at the soar period `T`.
+pub struct Simulator {
+    pose: Pose,
+    walls: Vec<Wall>,
+    model: RobotModel,
+}
+
+impl Simulator {
+    /// Simulates the Pioneer; see `with_model` for other robots.
+    pub fn new(pose: Pose, walls: Vec<Wall>) -> Self {
+        Self {
+            pose,
+            walls,
+            model: RobotModel::default(),
+        }
+    }
+
+    pub fn with_model(mut self, model: RobotModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Places the robot at the world's initial pose, walled in by its bounds.
+    pub fn from_world(world: &World) -> Self {
+        let mut walls = world.walls.clone();
+        walls.extend(world.boundary_walls());
+        Self::new(world.initial_pose.clone(), walls)
+    }
+
+    pub fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+
+    pub fn model(&self) -> &RobotModel {
+        &self.model
+    }
+
+    /// One reading per sonar of the model, `sonar_max` when nothing is in range.
+    pub fn sonars(&self) -> Vec<f64> {
+        let max = self.model.sonar_max;
+        self.model
+            .sonar_poses
+            .iter()
+            .map(|spose| {
+                let origin = self.pose.transform_point(spose.pos);
+                let dir = Point::from_polar(1.0, self.pose.theta + spose.theta);
+                self.walls
+                    .iter()
+                    .filter_map(|wall| wall.cast(origin, dir))
+                    .fold(max, f64::min)
+            })
+            .collect()
+    }
+
+    /// The sonar readings and the true pose.
+    pub fn sensor_input(&self) -> SensorInput {
+        debug_assert!(self.model.sonar_poses.len() <= MAX_SONARS);
+        let mut sonars = [self.model.sonar_max; MAX_SONARS];
+        for (reading, d) in sonars.iter_mut().zip(self.sonars()) {
+            *reading = d;
+        }
+        SensorInput {
+            sonars,
+            odometry: self.pose.clone(),
+        }
+    }
+
+    /// Integrates `action` over one period using the heading at mid-step.
+    pub fn apply(&mut self, action: &Action) {
+        let Pose { pos, theta } = self.pose;
+        let mid_theta = theta + action.rvel * T / 2.0;
+        self.pose = Pose {
+            pos: pos + Point::from_polar(action.fvel * T, mid_theta),
+            theta: Angle::new(theta + action.rvel * T).0,
+        };
+    }
+
+    /// Closes the loop for one tick: senses, steps `machine`, applies its action.
+    pub fn step<SM>(&mut self, machine: &mut SM) -> Option<Action>
+    where
+        SM: StateFullMachine<SensorInput, Action> + ?Sized,
+    {
+        let action = machine.step(Some(self.sensor_input()));
+        if let Some(action) = &action {
+            self.apply(action);
+        }
+        action
+    }
+
+    /// Steps `machine` until it is done or `max_steps` ticks have elapsed,
+    /// returning the poses visited after each tick.
+    pub fn run<SM>(&mut self, machine: &mut SM, max_steps: usize) -> Vec<Pose>
+    where
+        SM: StateFullMachine<SensorInput, Action> + ?Sized,
+    {
+        let mut trajectory = Vec::new();
+        for _ in 0..max_steps {
+            if machine.is_done() {
+                break;
+            }
+            self.step(machine);
+            trajectory.push(self.pose.clone());
+        }
+        trajectory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sm::StateMachine, sonars::get_distance_right};
+    use std::f64::consts::PI;
+
+    const EPSILON: f64 = 10e-9;
+
+    fn at(x: f64, y: f64, theta: f64) -> Pose {
+        Pose {
+            pos: Point::new(x, y),
+            theta,
+        }
+    }
+
+    #[test]
+    fn test_drive_straight() {
+        let mut sim = Simulator::new(at(0.0, 0.0, 0.0), vec![]);
+        for _ in 0..10 {
+            sim.apply(&Action::foward(0.1));
+        }
+        assert!((sim.pose().pos.x - 0.1).abs() < EPSILON);
+        assert!(sim.pose().pos.y.abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_rotate_in_place() {
+        let mut sim = Simulator::new(at(1.0, 1.0, 0.0), vec![]);
+        for _ in 0..10 {
+            sim.apply(&Action::rotate(PI / 2.0));
+        }
+        assert!((sim.pose().theta - PI / 2.0).abs() < EPSILON);
+        assert!(sim.pose().pos.is_near(Point::new(1.0, 1.0), EPSILON));
+    }
+
+    #[test]
+    fn test_no_walls_reads_max() {
+        let sim = Simulator::new(at(0.0, 0.0, 0.0), vec![]);
+        assert_eq!(sim.sensor_input().sonars, [1.5; 8]);
+    }
+
+    #[test]
+    fn test_parallel_wall_on_right() {
+        let wall = Wall::new(Point::new(-2.0, -0.5), Point::new(2.0, -0.5));
+        let sim = Simulator::new(at(0.0, 0.0, 0.0), vec![wall]);
+        let sonars = sim.sensor_input().sonars;
+        assert!((sonars[7] - (0.5 - 0.134)).abs() < EPSILON);
+        assert!((get_distance_right(&sonars) - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_wall_seen_from_rotated_robot() {
+        // same wall as above, robot and wall both rotated a quarter turn
+        let wall = Wall::new(Point::new(0.5, -2.0), Point::new(0.5, 2.0));
+        let sim = Simulator::new(at(0.0, 0.0, PI / 2.0), vec![wall]);
+        assert!((get_distance_right(&sim.sensor_input().sonars) - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_closed_loop_stops_before_wall() {
+        let wall = Wall::new(Point::new(1.0, -2.0), Point::new(1.0, 2.0));
+        let mut sim = Simulator::new(at(0.0, 0.0, 0.0), vec![wall]);
+        let mut brain = (|_: SensorInput| Action::foward(0.1))
+            .until(|input: SensorInput| input.sonars[3] < 0.5)
+            .into_state_full_machine();
+        let trajectory = sim.run(&mut brain, 1000);
+        assert!(brain.is_done());
+        assert!(trajectory.len() < 1000);
+        let x = sim.pose().pos.x;
+        assert!(0.33 < x && x < 0.36, "stopped at {x}");
+    }
+}
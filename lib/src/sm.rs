@@ -1,8 +1,16 @@
 use std::marker::PhantomData;
 
-use crate::sig::Signal;
+#[cfg(feature = "serde")]
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::{
+    dyn_sm::BoxedStateMachine,
+    sig::Signal,
+    trace::{Probe, Traced, Tracer, look_ahead},
+    undef::{OnUndefined, ParallelAdd, ParallelOpt, UndefinedPolicy},
+};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Either<L, R> {
     Left(L),
     Rigth(R),
@@ -12,9 +20,19 @@ pub trait StateFullMachine<In, Out> {
     fn reset(&mut self);
     fn step(&mut self, input: Option<In>) -> Option<Out>;
     fn is_done(&self) -> bool;
+
+    #[cfg(feature = "serde")]
+    fn snapshot(&self) -> Result<String, SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
+
+    #[cfg(feature = "serde")]
+    fn restore(&mut self, _snapshot: &str) -> Result<(), SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
 }
 
-pub struct StateFull<In, Out, SM>(SM::State, SM)
+pub struct StateFull<In, Out, SM>(pub(crate) SM::State, pub(crate) SM)
 where
     SM: StateMachine<In, Out>;
 
@@ -56,6 +74,12 @@ pub trait StateMachine<In, Out> {
         false
     }
 
+    /// Whether the machine can step from `state`, for states that come from
+    /// outside, such as a restored snapshot.
+    fn validate(&self, _state: &Self::State) -> bool {
+        true
+    }
+
     fn transduce<I, II: IntoIterator<Item = I>>(&self, inputs: II) -> impl Iterator<Item = Out>
     where
         Self: Sized,
@@ -73,6 +97,31 @@ pub trait StateMachine<In, Out> {
         })
     }
 
+    /// Like `transduce`, but leaves out the steps without an output, such
+    /// as those rejected by `filter`, instead of stopping at the first one.
+    fn transduce_defined<I, II: IntoIterator<Item = I>>(
+        &self,
+        inputs: II,
+    ) -> impl Iterator<Item = Out>
+    where
+        Self: Sized,
+        I: Into<In>,
+    {
+        let mut state = self.start_state();
+        inputs
+            .into_iter()
+            .map_while(move |input| {
+                if self.done(state.clone()) {
+                    None
+                } else {
+                    let (new_state, output) = self.next_values(state.clone(), Some(input.into()));
+                    state = new_state;
+                    Some(output)
+                }
+            })
+            .flatten()
+    }
+
     fn transduce_signal<Sg: Signal<Out = In>>(self, mut input_sig: Sg) -> impl Signal<Out = Out>
     where
         Self: Sized,
@@ -124,6 +173,91 @@ pub trait StateMachine<In, Out> {
         StateFull::new(self)
     }
 
+    /// Like `into_state_full_machine`, with `snapshot`/`restore` support.
+    #[cfg(feature = "serde")]
+    fn into_snapshot_machine(self) -> impl StateFullMachine<In, Out>
+    where
+        Self: Sized,
+        Self::State: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        Snapshot(StateFull::new(self))
+    }
+
+    fn boxed(self) -> BoxedStateMachine<In, Out>
+    where
+        Self: Sized + 'static,
+        Self::State: 'static,
+    {
+        BoxedStateMachine::new(self)
+    }
+
+    fn map_input<I, F>(self, f: F) -> MapInput<Self, F>
+    where
+        Self: Sized,
+        F: Fn(I) -> In,
+    {
+        MapInput { machine: self, f }
+    }
+
+    fn map_output<O, F>(self, f: F) -> MapOutput<Self, F, Out>
+    where
+        Self: Sized,
+        F: Fn(Out) -> O,
+    {
+        MapOutput {
+            machine: self,
+            f,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Outputs nothing on the steps whose output fails `pred`; use
+    /// `transduce_defined` to skip those steps.
+    fn filter<P>(self, pred: P) -> Filter<Self, P, Out>
+    where
+        Self: Sized,
+        P: Fn(&Out) -> bool,
+    {
+        Filter {
+            machine: self,
+            pred,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Outputs the running fold of the outputs, starting from `init` at
+    /// every (re)start.
+    fn scan<A, F>(self, init: A, f: F) -> Scan<Self, A, F, Out>
+    where
+        Self: Sized,
+        A: Clone,
+        F: Fn(A, Out) -> A,
+    {
+        Scan {
+            machine: self,
+            init,
+            f,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Like `parallel`, combining the two outputs with `f`.
+    fn zip_with<SM, O2, O, F>(self, machine: SM, f: F) -> ZipWith<Self, SM, F, (Out, O2)>
+    where
+        Self: Sized,
+        In: Clone,
+        SM: StateMachine<In, O2>,
+        F: Fn(Out, O2) -> O,
+    {
+        ZipWith {
+            first_machine: self,
+            second_machine: machine,
+            f,
+            policy: DonePolicy::Any,
+            _phantom: PhantomData,
+        }
+    }
+
     fn cascade<O, SM>(self, sm: SM) -> Cascade<Self, SM, O>
     where
         Self: Sized,
@@ -131,6 +265,7 @@ pub trait StateMachine<In, Out> {
         Cascade {
             first_machine: self,
             second_machine: sm,
+            policy: DonePolicy::Any,
             _phantom: PhantomData,
         }
     }
@@ -144,6 +279,46 @@ pub trait StateMachine<In, Out> {
         Parallel {
             machine1: self,
             machine2: next_machine,
+            policy: DonePolicy::Any,
+        }
+    }
+
+    /// Like `parallel`, but pairs the outputs as `Option`s instead of
+    /// leaving the step undefined when either one is.
+    fn parallel_opt<SM, O>(self, machine: SM) -> ParallelOpt<Self, SM>
+    where
+        In: Clone,
+        Self: Sized,
+        SM: StateMachine<In, O>,
+    {
+        ParallelOpt {
+            machine1: self,
+            machine2: machine,
+            policy: DonePolicy::Any,
+        }
+    }
+
+    /// Adds the `Option` outputs of two machines run on the same input; see
+    /// `undef`.
+    fn parallel_add<SM>(self, machine: SM) -> ParallelAdd<Self, SM>
+    where
+        In: Clone,
+        Self: Sized,
+    {
+        ParallelAdd {
+            machine1: self,
+            machine2: machine,
+        }
+    }
+
+    /// Replaces undefined `Option` outputs according to `policy`.
+    fn on_undefined<T>(self, policy: UndefinedPolicy<T>) -> OnUndefined<Self, T>
+    where
+        Self: Sized,
+    {
+        OnUndefined {
+            machine: self,
+            policy,
         }
     }
 
@@ -175,6 +350,49 @@ pub trait StateMachine<In, Out> {
         }
     }
 
+    /// `feedback`, failing if the loop has no delay and would output
+    /// `None` forever.
+    fn try_feedback(self) -> Result<Feedback<Self>, AlgebraicLoopError>
+    where
+        In: Clone,
+        Self: Sized + StateMachine<In, In>,
+    {
+        let feedback = Feedback { machine: self };
+        feedback.check_loop::<In>()?;
+        Ok(feedback)
+    }
+
+    /// `feedback2`, failing if the loop has no delay.
+    fn try_feedback2<I>(self) -> Result<Feedback2<Self>, AlgebraicLoopError>
+    where
+        Self: Sized + StateMachine<(I, Out), Out>,
+        Out: Clone,
+    {
+        let feedback = Feedback2 { machine: self };
+        feedback.check_loop::<I, Out>()?;
+        Ok(feedback)
+    }
+
+    /// `feedback_op`, failing if the loop has no delay.
+    fn try_feedback_op<SM, Op, Out2>(
+        self,
+        machine: SM,
+        op: Op,
+    ) -> Result<FeedbackOp<Self, SM, Op, In, Out2>, AlgebraicLoopError>
+    where
+        Self: Sized,
+        SM: StateMachine<Out, Out2>,
+    {
+        let feedback = FeedbackOp {
+            first_machine: self,
+            second_machine: machine,
+            op,
+            _phantom: PhantomData,
+        };
+        feedback.check_loop::<Out>()?;
+        Ok(feedback)
+    }
+
     fn switch<SM, P>(self, machine: SM, pred: P) -> Switch<Self, SM, P>
     where
         Self: Sized,
@@ -186,6 +404,7 @@ pub trait StateMachine<In, Out> {
             first_machine: self,
             second_machine: machine,
             pred,
+            policy: DonePolicy::Any,
         }
     }
 
@@ -200,6 +419,7 @@ pub trait StateMachine<In, Out> {
             first_machine: self,
             second_machine: machine,
             pred,
+            policy: DonePolicy::Any,
         }
     }
 
@@ -258,6 +478,30 @@ pub trait StateMachine<In, Out> {
             pred,
         }
     }
+
+    fn traced(self, name: &str, tracer: &Tracer) -> Traced<Self>
+    where
+        Self: Sized,
+    {
+        Traced {
+            machine: self,
+            name: name.to_string(),
+            tracer: tracer.clone(),
+        }
+    }
+
+    fn probe<F>(self, name: &str, tracer: &Tracer, f: F) -> Probe<Self, F>
+    where
+        Self: Sized,
+        F: Fn(&Out) -> f64,
+    {
+        Probe {
+            machine: self,
+            name: name.to_string(),
+            tracer: tracer.clone(),
+            f,
+        }
+    }
 }
 
 impl<In, Out, F> StateMachine<In, Out> for F
@@ -327,6 +571,190 @@ where
     }
 }
 
+pub struct MapInput<SM, F> {
+    machine: SM,
+    f: F,
+}
+
+impl<I, In, Out, SM, F> StateMachine<I, Out> for MapInput<SM, F>
+where
+    SM: StateMachine<In, Out>,
+    F: Fn(I) -> In,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<I>) -> (Self::State, Option<Out>) {
+        self.machine.next_values(state, input.map(&self.f))
+    }
+}
+
+pub struct MapOutput<SM, F, O> {
+    machine: SM,
+    f: F,
+    _phantom: PhantomData<O>,
+}
+
+impl<In, O, Out, SM, F> StateMachine<In, Out> for MapOutput<SM, F, O>
+where
+    SM: StateMachine<In, O>,
+    F: Fn(O) -> Out,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let (new_state, output) = self.machine.next_values(state, input);
+        (new_state, output.map(&self.f))
+    }
+}
+
+pub struct Filter<SM, P, O> {
+    machine: SM,
+    pred: P,
+    _phantom: PhantomData<O>,
+}
+
+impl<In, Out, SM, P> StateMachine<In, Out> for Filter<SM, P, Out>
+where
+    SM: StateMachine<In, Out>,
+    P: Fn(&Out) -> bool,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let (new_state, output) = self.machine.next_values(state, input);
+        (new_state, output.filter(&self.pred))
+    }
+}
+
+pub struct Scan<SM, A, F, O> {
+    machine: SM,
+    init: A,
+    f: F,
+    _phantom: PhantomData<O>,
+}
+
+impl<In, O, A, SM, F> StateMachine<In, A> for Scan<SM, A, F, O>
+where
+    A: Clone,
+    SM: StateMachine<In, O>,
+    F: Fn(A, O) -> A,
+{
+    type State = (SM::State, A);
+
+    fn start_state(&self) -> Self::State {
+        (self.machine.start_state(), self.init.clone())
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state.0)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(&state.0)
+    }
+
+    fn next_values(
+        &self,
+        (state, acc): Self::State,
+        input: Option<In>,
+    ) -> (Self::State, Option<A>) {
+        match self.machine.next_values(state, input) {
+            (new_state, Some(output)) => {
+                let acc = (self.f)(acc, output);
+                ((new_state, acc.clone()), Some(acc))
+            }
+            (new_state, None) => ((new_state, acc), None),
+        }
+    }
+}
+
+pub struct ZipWith<SM1, SM2, F, O> {
+    first_machine: SM1,
+    second_machine: SM2,
+    f: F,
+    policy: DonePolicy,
+    _phantom: PhantomData<O>,
+}
+
+impl<SM1, SM2, F, O> ZipWith<SM1, SM2, F, O> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
+impl<In, O1, O2, Out, SM1, SM2, F> StateMachine<In, Out> for ZipWith<SM1, SM2, F, (O1, O2)>
+where
+    In: Clone,
+    SM1: StateMachine<In, O1>,
+    SM2: StateMachine<In, O2>,
+    F: Fn(O1, O2) -> Out,
+{
+    type State = (SM1::State, SM2::State);
+
+    fn start_state(&self) -> Self::State {
+        (
+            self.first_machine.start_state(),
+            self.second_machine.start_state(),
+        )
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.policy.done(
+            || self.first_machine.done(state.0),
+            || self.second_machine.done(state.1),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.first_machine.validate(&state.0) && self.second_machine.validate(&state.1)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let (new_s1, o1) = self.first_machine.next_values(state.0, input.clone());
+        let (new_s2, o2) = self.second_machine.next_values(state.1, input);
+        (
+            (new_s1, new_s2),
+            o1.zip(o2).map(|(o1, o2)| (self.f)(o1, o2)),
+        )
+    }
+}
+
 pub struct Until<SM, P> {
     machine: SM,
     pred: P,
@@ -349,6 +777,10 @@ where
         self.machine.done(state) || done
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(&state.0)
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         let (state, _) = state;
         let done = input
@@ -382,6 +814,10 @@ where
         self.machine.done(state) && done
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(&state.0)
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         let (state, _) = state;
         let done = input
@@ -416,6 +852,10 @@ where
         self.n == Some(state.0)
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.n.is_none_or(|n| state.0 <= n) && self.machine.validate(&state.1)
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         let (mut n, state) = state;
         let (mut new_s, out) = self.machine.next_values(state, input);
@@ -461,6 +901,13 @@ where
         }
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        match state {
+            Either::Left(s1) => self.first_machine.validate(s1),
+            Either::Rigth(s2) => self.second_machine.validate(s2),
+        }
+    }
+
     fn next_values(&self, mut state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         if let Either::Left(s1) = state {
             if !self.first_machine.done(s1.clone()) {
@@ -481,6 +928,92 @@ where
     }
 }
 
+/// `Seq` over a runtime list of machines of the same type (boxed ones for
+/// heterogeneous lists). Done once the last machine is done; an empty list
+/// is done from the start.
+pub struct SeqN<SM> {
+    machines: Vec<SM>,
+}
+
+impl<SM> SeqN<SM> {
+    pub fn new(machines: Vec<SM>) -> Self {
+        Self { machines }
+    }
+
+    /// Skips over the machines that are done, stopping at the last one.
+    fn current<In, Out>(&self, (mut i, mut state): (usize, SM::State)) -> (usize, SM::State)
+    where
+        SM: StateMachine<In, Out>,
+    {
+        while i + 1 < self.machines.len() && self.machines[i].done(state.clone()) {
+            i += 1;
+            state = self.machines[i].start_state();
+        }
+        (i, state)
+    }
+}
+
+impl<SM> FromIterator<SM> for SeqN<SM> {
+    fn from_iter<I: IntoIterator<Item = SM>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+pub fn seq_from_iter<SM, I: IntoIterator<Item = SM>>(machines: I) -> SeqN<SM> {
+    machines.into_iter().collect()
+}
+
+/// Runs the list `n` times (forever if `None`), with `Repeat`'s semantics.
+pub fn seq_repeat<SM, I: IntoIterator<Item = SM>>(
+    machines: I,
+    n: Option<usize>,
+) -> Repeat<SeqN<SM>> {
+    Repeat {
+        machine: seq_from_iter(machines),
+        n,
+    }
+}
+
+impl<In, Out, SM> StateMachine<In, Out> for SeqN<SM>
+where
+    SM: StateMachine<In, Out>,
+{
+    type State = Option<(usize, SM::State)>;
+
+    fn start_state(&self) -> Self::State {
+        self.machines.first().map(|m| (0, m.start_state()))
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        match state {
+            Some(state) => {
+                let (i, state) = self.current(state);
+                self.machines[i].done(state)
+            }
+            None => true,
+        }
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        match state {
+            Some((i, state)) => self.machines.get(*i).is_some_and(|m| m.validate(state)),
+            None => self.machines.is_empty(),
+        }
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let Some(state) = state else {
+            return (None, None);
+        };
+        let (i, state) = self.current(state);
+        if self.machines[i].done(state.clone()) {
+            return (Some((i, state)), None);
+        }
+        let (new_state, out) = self.machines[i].next_values(state, input);
+        (Some((i, new_state)), out)
+    }
+}
+
 #[derive(Debug)]
 pub struct If<SM1, SM2, P> {
     first_machine: SM1,
@@ -509,6 +1042,14 @@ where
         }
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        match state {
+            Some(Either::Left(s1)) => self.first_machine.validate(s1),
+            Some(Either::Rigth(s2)) => self.second_machine.validate(s2),
+            None => true,
+        }
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         let state = match state {
             Some(state) => state,
@@ -534,10 +1075,100 @@ where
     }
 }
 
+/// When a combinator of several machines is done. `Any` is the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DonePolicy {
+    #[default]
+    Any,
+    All,
+    First,
+    Second,
+}
+
+impl DonePolicy {
+    pub(crate) fn done(self, first: impl FnOnce() -> bool, second: impl FnOnce() -> bool) -> bool {
+        match self {
+            DonePolicy::Any => first() || second(),
+            DonePolicy::All => first() && second(),
+            DonePolicy::First => first(),
+            DonePolicy::Second => second(),
+        }
+    }
+}
+
+/// `Parallel` over any number of machines of the same type; outputs the
+/// vector of their outputs. `First` and `Second` look at the first and
+/// second machines.
+pub struct ParallelN<SM> {
+    machines: Vec<SM>,
+    policy: DonePolicy,
+}
+
+impl<SM> ParallelN<SM> {
+    pub fn new(machines: Vec<SM>) -> Self {
+        Self {
+            machines,
+            policy: DonePolicy::Any,
+        }
+    }
+
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
+impl<In, Out, SM> StateMachine<In, Vec<Out>> for ParallelN<SM>
+where
+    In: Clone,
+    SM: StateMachine<In, Out>,
+{
+    type State = Vec<SM::State>;
+
+    fn start_state(&self) -> Self::State {
+        self.machines.iter().map(|m| m.start_state()).collect()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        let mut done = self.machines.iter().zip(state).map(|(m, s)| m.done(s));
+        match self.policy {
+            DonePolicy::Any => done.any(|d| d),
+            DonePolicy::All => done.all(|d| d),
+            DonePolicy::First => done.next().unwrap_or(false),
+            DonePolicy::Second => done.nth(1).unwrap_or(false),
+        }
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        state.len() == self.machines.len()
+            && self.machines.iter().zip(state).all(|(m, s)| m.validate(s))
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<In>,
+    ) -> (Self::State, Option<Vec<Out>>) {
+        let (new_state, outputs): (Vec<_>, Vec<_>) = self
+            .machines
+            .iter()
+            .zip(state)
+            .map(|(m, s)| m.next_values(s, input.clone()))
+            .unzip();
+        (new_state, outputs.into_iter().collect())
+    }
+}
+
 pub struct Mux<SM1, SM2, P> {
     first_machine: SM1,
     second_machine: SM2,
     pred: P,
+    policy: DonePolicy,
+}
+
+impl<SM1, SM2, P> Mux<SM1, SM2, P> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
 }
 
 impl<In, Out, SM1, SM2, P> StateMachine<In, Out> for Mux<SM1, SM2, P>
@@ -557,7 +1188,14 @@ where
     }
 
     fn done(&self, state: Self::State) -> bool {
-        self.first_machine.done(state.0) || self.second_machine.done(state.1)
+        self.policy.done(
+            || self.first_machine.done(state.0),
+            || self.second_machine.done(state.1),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.first_machine.validate(&state.0) && self.second_machine.validate(&state.1)
     }
 
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
@@ -579,6 +1217,13 @@ pub struct Switch<SM1, SM2, P> {
     first_machine: SM1,
     second_machine: SM2,
     pred: P,
+    policy: DonePolicy,
+}
+
+impl<SM1, SM2, P> Switch<SM1, SM2, P> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
 }
 
 impl<In, Out, SM1, SM2, P> StateMachine<In, Out> for Switch<SM1, SM2, P>
@@ -598,7 +1243,14 @@ where
     }
 
     fn done(&self, state: Self::State) -> bool {
-        self.first_machine.done(state.0) || self.second_machine.done(state.1)
+        self.policy.done(
+            || self.first_machine.done(state.0),
+            || self.second_machine.done(state.1),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.first_machine.validate(&state.0) && self.second_machine.validate(&state.1)
     }
 
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
@@ -617,6 +1269,26 @@ pub struct Feedback2<SM> {
     machine: SM,
 }
 
+impl<SM> Feedback2<SM> {
+    pub fn check_loop<In, Out>(&self) -> Result<(), AlgebraicLoopError>
+    where
+        SM: StateMachine<(In, Out), Out>,
+    {
+        match look_ahead(|| self.machine.next_values(self.machine.start_state(), None)) {
+            (_, Some(_)) => Ok(()),
+            (_, None) => Err(AlgebraicLoopError("Feedback2")),
+        }
+    }
+
+    pub fn fixed_point(self, tol: f64, max_iter: usize) -> FixedPoint<Self> {
+        FixedPoint {
+            machine: self,
+            tol,
+            max_iter,
+        }
+    }
+}
+
 impl<In, Out, SM> StateMachine<In, Out> for Feedback2<SM>
 where
     SM: StateMachine<(In, Out), Out>,
@@ -632,17 +1304,44 @@ where
         self.machine.done(state)
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
-        let (_, output) = self.machine.next_values(state.clone(), None);
+        let (_, output) = look_ahead(|| self.machine.next_values(state.clone(), None));
         let (new_state, _) = self.machine.next_values(state, input.zip(output.clone()));
         (new_state, output)
     }
 }
 
+/// Feeds the machine's output back as its input. A loop without a delay
+/// outputs `None` forever: build it with `try_feedback` to catch that, or
+/// use `fixed_point` to solve it.
 pub struct Feedback<SM> {
     machine: SM,
 }
 
+impl<SM> Feedback<SM> {
+    pub fn check_loop<I>(&self) -> Result<(), AlgebraicLoopError>
+    where
+        SM: StateMachine<I, I>,
+    {
+        match look_ahead(|| self.machine.next_values(self.machine.start_state(), None)) {
+            (_, Some(_)) => Ok(()),
+            (_, None) => Err(AlgebraicLoopError("Feedback")),
+        }
+    }
+
+    pub fn fixed_point(self, tol: f64, max_iter: usize) -> FixedPoint<Self> {
+        FixedPoint {
+            machine: self,
+            tol,
+            max_iter,
+        }
+    }
+}
+
 impl<I, SM> StateMachine<I, I> for Feedback<SM>
 where
     I: Clone,
@@ -658,8 +1357,12 @@ where
         self.machine.done(state)
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
     fn next_values(&self, state: Self::State, _input: Option<I>) -> (Self::State, Option<I>) {
-        let (_, output) = self.machine.next_values(state.clone(), None);
+        let (_, output) = look_ahead(|| self.machine.next_values(state.clone(), None));
         let (new_state, _) = self.machine.next_values(state, output.clone());
         (new_state, output)
     }
@@ -672,6 +1375,34 @@ pub struct FeedbackOp<SM1, SM2, Op, I, O> {
     _phantom: PhantomData<(I, O)>,
 }
 
+impl<SM1, SM2, Op, In1, Out2> FeedbackOp<SM1, SM2, Op, In1, Out2> {
+    pub fn check_loop<Out>(&self) -> Result<(), AlgebraicLoopError>
+    where
+        SM1: StateMachine<In1, Out>,
+        SM2: StateMachine<Out, Out2>,
+    {
+        let looked_ahead = look_ahead(|| {
+            let (_, o1) = self
+                .first_machine
+                .next_values(self.first_machine.start_state(), None);
+            self.second_machine
+                .next_values(self.second_machine.start_state(), o1)
+        });
+        match looked_ahead {
+            (_, Some(_)) => Ok(()),
+            (_, None) => Err(AlgebraicLoopError("FeedbackOp")),
+        }
+    }
+
+    pub fn fixed_point(self, tol: f64, max_iter: usize) -> FixedPoint<Self> {
+        FixedPoint {
+            machine: self,
+            tol,
+            max_iter,
+        }
+    }
+}
+
 impl<In, Out, In1, Out2, SM1, SM2, Op> StateMachine<In, Out> for FeedbackOp<SM1, SM2, Op, In1, Out2>
 where
     Out: Clone,
@@ -693,10 +1424,16 @@ where
         self.first_machine.done(s1) || self.second_machine.done(s2)
     }
 
+    fn validate(&self, state: &Self::State) -> bool {
+        self.first_machine.validate(&state.0) && self.second_machine.validate(&state.1)
+    }
+
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
         let (s1, s2) = state;
-        let (_, o1) = self.first_machine.next_values(s1.clone(), None);
-        let (_, o2) = self.second_machine.next_values(s2.clone(), o1);
+        let (_, o2) = look_ahead(|| {
+            let (_, o1) = self.first_machine.next_values(s1.clone(), None);
+            self.second_machine.next_values(s2.clone(), o1)
+        });
         let (new_s1, output) = self
             .first_machine
             .next_values(s1, input.zip(o2).map(|(i, i2)| (self.op)(i, i2)));
@@ -705,9 +1442,220 @@ where
     }
 }
 
+/// The output of a feedback loop depends on its own input: no machine in
+/// the loop has an output determined by its state alone, such as a delay.
+#[derive(Debug, Clone, Copy)]
+pub struct AlgebraicLoopError(&'static str);
+
+impl std::fmt::Display for AlgebraicLoopError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "algebraic loop in `{}`: add a delay or use `fixed_point`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for AlgebraicLoopError {}
+
+/// Values a fixed-point iteration can test for convergence.
+pub trait Converge {
+    fn distance(&self, other: &Self) -> f64;
+}
+
+impl Converge for f64 {
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
+impl Converge for f32 {
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).abs() as f64
+    }
+}
+
+impl<A: Converge, B: Converge> Converge for (A, B) {
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.distance(&other.0).max(self.1.distance(&other.1))
+    }
+}
+
+/// A feedback loop solved at every step by iterating its machines until the
+/// loop output moves by at most `tol`, starting from the state-determined
+/// output if there is one and from `Default` otherwise. A step that has not
+/// converged after `max_iter` iterations outputs `None`.
+pub struct FixedPoint<FB> {
+    machine: FB,
+    tol: f64,
+    max_iter: usize,
+}
+
+impl<FB> FixedPoint<FB> {
+    fn solve<T: Converge + Clone>(&self, guess: T, f: impl Fn(T) -> Option<T>) -> Option<T> {
+        let mut y = guess;
+        for _ in 0..self.max_iter {
+            let next = f(y.clone())?;
+            if next.distance(&y) <= self.tol {
+                return Some(next);
+            }
+            y = next;
+        }
+        None
+    }
+}
+
+impl<I, SM> StateMachine<I, I> for FixedPoint<Feedback<SM>>
+where
+    I: Converge + Default + Clone,
+    SM: StateMachine<I, I>,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<I>) -> (Self::State, Option<I>) {
+        let machine = &self.machine.machine;
+        let Some(_) = input else {
+            return machine.next_values(state, None);
+        };
+        let output = look_ahead(|| {
+            let (_, guess) = machine.next_values(state.clone(), None);
+            self.solve(guess.unwrap_or_default(), |y| {
+                machine.next_values(state.clone(), Some(y)).1
+            })
+        });
+        match output {
+            Some(output) => {
+                let (new_state, _) = machine.next_values(state, Some(output.clone()));
+                (new_state, Some(output))
+            }
+            None => (state, None),
+        }
+    }
+}
+
+impl<In, Out, SM> StateMachine<In, Out> for FixedPoint<Feedback2<SM>>
+where
+    In: Clone,
+    Out: Converge + Default + Clone,
+    SM: StateMachine<(In, Out), Out>,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let machine = &self.machine.machine;
+        let Some(input) = input else {
+            return machine.next_values(state, None);
+        };
+        let output = look_ahead(|| {
+            let (_, guess) = machine.next_values(state.clone(), None);
+            self.solve(guess.unwrap_or_default(), |y| {
+                machine
+                    .next_values(state.clone(), Some((input.clone(), y)))
+                    .1
+            })
+        });
+        match output {
+            Some(output) => {
+                let (new_state, _) = machine.next_values(state, Some((input, output.clone())));
+                (new_state, Some(output))
+            }
+            None => (state, None),
+        }
+    }
+}
+
+impl<In, Out, In1, Out2, SM1, SM2, Op> StateMachine<In, Out>
+    for FixedPoint<FeedbackOp<SM1, SM2, Op, In1, Out2>>
+where
+    In: Clone,
+    Out: Converge + Default + Clone,
+    Op: Fn(In, Out2) -> In1,
+    SM1: StateMachine<In1, Out>,
+    SM2: StateMachine<Out, Out2>,
+{
+    type State = (SM1::State, SM2::State);
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let FeedbackOp {
+            first_machine,
+            second_machine,
+            op,
+            ..
+        } = &self.machine;
+        let Some(input) = input else {
+            return self.machine.next_values(state, None);
+        };
+        let (s1, s2) = state;
+        let output = look_ahead(|| {
+            let (_, guess) = first_machine.next_values(s1.clone(), None);
+            self.solve(guess.unwrap_or_default(), |y| {
+                let (_, o2) = second_machine.next_values(s2.clone(), Some(y));
+                first_machine
+                    .next_values(s1.clone(), o2.map(|o2| op(input.clone(), o2)))
+                    .1
+            })
+        });
+        match output {
+            Some(output) => {
+                let (new_s2, o2) = second_machine.next_values(s2, Some(output.clone()));
+                let (new_s1, _) = first_machine.next_values(s1, o2.map(|o2| op(input, o2)));
+                ((new_s1, new_s2), Some(output))
+            }
+            None => ((s1, s2), None),
+        }
+    }
+}
+
+/// Runs two machines on the same input and pairs their outputs. The step is
+/// undefined if either output is: use `parallel_opt` to keep the defined one,
+/// or `parallel_add` and `on_undefined` for `Option` outputs.
 pub struct Parallel<SM1, SM2> {
     machine1: SM1,
     machine2: SM2,
+    policy: DonePolicy,
+}
+
+impl<SM1, SM2> Parallel<SM1, SM2> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
 }
 
 impl<In, Out1, Out2, SM1, SM2> StateMachine<In, (Out1, Out2)> for Parallel<SM1, SM2>
@@ -723,7 +1671,14 @@ where
     }
 
     fn done(&self, state: Self::State) -> bool {
-        self.machine1.done(state.0) || self.machine2.done(state.1)
+        self.policy.done(
+            || self.machine1.done(state.0),
+            || self.machine2.done(state.1),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine1.validate(&state.0) && self.machine2.validate(&state.1)
     }
 
     fn next_values(
@@ -744,9 +1699,16 @@ where
 pub struct Cascade<SM1, SM2, O> {
     first_machine: SM1,
     second_machine: SM2,
+    policy: DonePolicy,
     _phantom: PhantomData<O>,
 }
 
+impl<SM1, SM2, O> Cascade<SM1, SM2, O> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
 impl<In, O, Out, SM1, SM2> StateMachine<In, Out> for Cascade<SM1, SM2, O>
 where
     SM1: StateMachine<In, O>,
@@ -763,7 +1725,14 @@ where
 
     fn done(&self, state: Self::State) -> bool {
         let (s1, s2) = state;
-        self.first_machine.done(s1) || self.second_machine.done(s2)
+        self.policy.done(
+            || self.first_machine.done(s1),
+            || self.second_machine.done(s2),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.first_machine.validate(&state.0) && self.second_machine.validate(&state.1)
     }
 
     fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
@@ -1,8 +1,12 @@
-use std::ops::Mul;
+use std::ops::{Add, Index, Mul, Sub};
 
-use crate::sm::StateMachine;
+use crate::sm::{FeedbackOp, StateMachine, ZipWith};
 
-struct Delay<I>(I);
+// Counterparts of the lib601 `sm` primitives. Like lib601 they output a
+// value on every step, except `Delay` (`R`) and `Constant`, whose output
+// only depends on their state and which therefore can close a feedback loop.
+
+pub struct Delay<I>(I);
 impl<I: Clone> StateMachine<I, I> for Delay<I> {
     type State = Option<I>;
 
@@ -15,24 +19,220 @@ impl<I: Clone> StateMachine<I, I> for Delay<I> {
     }
 }
 
-pub fn delay<I: Clone>(val0: I) -> impl StateMachine<I, I> {
+pub fn delay<I: Clone>(val0: I) -> Delay<I> {
+    Delay(val0)
+}
+
+/// lib601's name for `Delay`.
+pub type R<I> = Delay<I>;
+
+pub fn r<I: Clone>(val0: I) -> R<I> {
     Delay(val0)
 }
 
-pub fn wire<I>() -> impl StateMachine<I, I> {
-    |input| input
+pub struct Wire;
+impl<I> StateMachine<I, I> for Wire {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<I>) -> (Self::State, Option<I>) {
+        (state, input)
+    }
+}
+
+pub fn wire<I>() -> impl StateMachine<I, I, State = ()> {
+    Wire
+}
+
+/// Same as `gain`.
+pub fn scale<I: Mul<I, Output = I> + Clone>(k: I) -> Gain<I> {
+    gain(k)
+}
+
+pub struct Gain<T>(T);
+impl<T: Mul<Output = T> + Clone> StateMachine<T, T> for Gain<T> {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<T>) -> (Self::State, Option<T>) {
+        (state, input.map(|input| self.0.clone() * input))
+    }
+}
+
+pub fn gain<T: Mul<Output = T> + Clone>(k: T) -> Gain<T> {
+    Gain(k)
+}
+
+/// Adds the two components of its input.
+pub struct Adder;
+impl<T: Add<Output = T>> StateMachine<(T, T), T> for Adder {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<(T, T)>) -> (Self::State, Option<T>) {
+        (state, input.map(|(a, b)| a + b))
+    }
+}
+
+pub fn adder() -> Adder {
+    Adder
+}
+
+pub struct Increment<T>(T);
+impl<T: Add<Output = T> + Clone> StateMachine<T, T> for Increment<T> {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<T>) -> (Self::State, Option<T>) {
+        (state, input.map(|input| input + self.0.clone()))
+    }
+}
+
+pub fn increment<T: Add<Output = T> + Clone>(incr: T) -> Increment<T> {
+    Increment(incr)
+}
+
+/// Logical not.
+pub struct Negation;
+impl StateMachine<bool, bool> for Negation {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<bool>) -> (Self::State, Option<bool>) {
+        (state, input.map(|input| !input))
+    }
+}
+
+pub fn negation() -> Negation {
+    Negation
+}
+
+/// Running sum of the inputs, starting from `T::default()`.
+pub struct Sum;
+impl<T: Add<Output = T> + Default + Clone> StateMachine<T, T> for Sum {
+    type State = T;
+
+    fn start_state(&self) -> Self::State {
+        T::default()
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<T>) -> (Self::State, Option<T>) {
+        match input {
+            Some(input) => {
+                let sum = state + input;
+                (sum.clone(), Some(sum))
+            }
+            None => (state, None),
+        }
+    }
 }
 
-pub fn scale<I: Mul<I, Output = I> + Clone + 'static>(gain: I) -> impl StateMachine<I, I> {
-    move |input| gain.clone() * input
+pub fn sum() -> Sum {
+    Sum
+}
+
+/// Outputs the `k`-th element of its input.
+pub struct Select(usize);
+impl<In, T> StateMachine<In, T> for Select
+where
+    In: Index<usize, Output = T>,
+    T: Clone,
+{
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<T>) {
+        (state, input.map(|input| input[self.0].clone()))
+    }
+}
+
+pub fn select(k: usize) -> Select {
+    Select(k)
+}
+
+/// Outputs `c` whatever its input.
+pub struct Constant<T>(T);
+impl<In, T: Clone> StateMachine<In, T> for Constant<T> {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, _input: Option<In>) -> (Self::State, Option<T>) {
+        (state, Some(self.0.clone()))
+    }
+}
+
+pub fn constant<T: Clone>(c: T) -> Constant<T> {
+    Constant(c)
+}
+
+pub struct PureFunction<F>(F);
+impl<In, Out, F: Fn(In) -> Out> StateMachine<In, Out> for PureFunction<F> {
+    type State = ();
+
+    fn start_state(&self) -> Self::State {}
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        (state, input.map(&self.0))
+    }
+}
+
+pub fn pure_function<In, Out, F: Fn(In) -> Out>(f: F) -> PureFunction<F> {
+    PureFunction(f)
+}
+
+pub type BinOp<T> = fn(T, T) -> T;
+
+/// Runs `m1` and `m2` on the same input and adds their outputs; the
+/// state-machine counterpart of `SystemFunction::feedforward_add`. See
+/// `StateMachine::parallel_add` for `Option` outputs.
+pub fn feedforward_add<In, T, SM1, SM2>(m1: SM1, m2: SM2) -> ZipWith<SM1, SM2, BinOp<T>, (T, T)>
+where
+    In: Clone,
+    T: Add<Output = T>,
+    SM1: StateMachine<In, T>,
+    SM2: StateMachine<In, T>,
+{
+    m1.zip_with(m2, T::add)
+}
+
+/// Feeds the output of `m1` back through `m2` and adds it to the input of
+/// `m1`; the state-machine counterpart of `SystemFunction::feedback_add`.
+pub fn feedback_add<T, SM1, SM2>(m1: SM1, m2: SM2) -> FeedbackOp<SM1, SM2, BinOp<T>, T, T>
+where
+    T: Add<Output = T> + Clone,
+    SM1: StateMachine<T, T>,
+    SM2: StateMachine<T, T>,
+{
+    m1.feedback_op(m2, T::add)
+}
+
+/// Like `feedback_add`, subtracting the fed back value; the counterpart of
+/// `SystemFunction::feedback_sub`.
+pub fn feedback_subtract<T, SM1, SM2>(m1: SM1, m2: SM2) -> FeedbackOp<SM1, SM2, BinOp<T>, T, T>
+where
+    T: Sub<Output = T> + Clone,
+    SM1: StateMachine<T, T>,
+    SM2: StateMachine<T, T>,
+{
+    m1.feedback_op(m2, T::sub)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         sig::IterSignal,
-        sm::{StateFullMachine, StateMachine},
-        sm_course::{delay, wire},
+        sm::{DonePolicy, ParallelN, StateFullMachine, StateMachine, seq_from_iter, seq_repeat},
+        sm_course::{
+            adder, constant, delay, feedback_add, feedback_subtract, feedforward_add, gain,
+            increment, negation, pure_function, r, scale, select, sum, wire,
+        },
     };
     use std::ops::Add;
 
@@ -358,6 +558,453 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_algebraic_loop() {
+        assert!(
+            wire()
+                .feedback_op(delay(0), |i1: i32, i2: i32| i1 + i2)
+                .check_loop()
+                .is_ok()
+        );
+        assert!(
+            delay(1)
+                .feedback_op(wire(), |i1: i32, i2: i32| i1 * i2)
+                .check_loop()
+                .is_ok()
+        );
+        assert!(delay(1).feedback().check_loop().is_ok());
+        assert!(
+            (|(i, o): (i32, i32)| i + o)
+                .cascade(delay(0))
+                .feedback2()
+                .check_loop()
+                .is_ok()
+        );
+
+        let err = wire()
+            .feedback_op(scale(0.5), |i1: f64, i2: f64| i1 + i2)
+            .check_loop()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "algebraic loop in `FeedbackOp`: add a delay or use `fixed_point`"
+        );
+        assert!(scale(1).feedback().check_loop().is_err());
+        assert!(
+            (|(i, o): (i32, i32)| i + o)
+                .feedback2()
+                .check_loop()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_try_feedback() {
+        let err = wire()
+            .try_feedback_op(scale(0.5), |i1: f64, i2: f64| i1 + i2)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "algebraic loop in `FeedbackOp`: add a delay or use `fixed_point`"
+        );
+        assert!(scale(1).try_feedback().is_err());
+        assert!((|(i, o): (i32, i32)| i + o).try_feedback2().is_err());
+
+        let sm = delay(1).try_feedback_op(wire(), |i1: i32, i2: i32| i1 + i2);
+        let out = sm.unwrap().transduce([1, 2, 3]).collect::<Vec<_>>();
+        assert_eq!(out, [1, 2, 4]);
+        assert!(delay(1).try_feedback().is_ok());
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        // y = x + y / 2
+        let sm = wire()
+            .feedback_op(scale(0.5), |i1: f64, i2: f64| i1 + i2)
+            .fixed_point(1e-9, 100);
+        let out = sm.transduce([1.0, 2.0, -4.0]).collect::<Vec<_>>();
+        for (y, expected) in out.iter().zip([2.0, 4.0, -8.0]) {
+            assert!((y - expected).abs() < 1e-8);
+        }
+
+        // y = cos(y)
+        let sm = (|y: f64| y.cos()).feedback().fixed_point(1e-12, 200);
+        let y = sm.transduce([0.0]).next().unwrap();
+        assert!((y - y.cos()).abs() < 1e-10);
+
+        // y = (x + y) / 3
+        let sm = (|(x, y): (f64, f64)| (x + y) / 3.0)
+            .feedback2()
+            .fixed_point(1e-12, 100);
+        let out = sm.transduce([2.0, 4.0]).collect::<Vec<_>>();
+        assert!((out[0] - 1.0).abs() < 1e-10 && (out[1] - 2.0).abs() < 1e-10);
+
+        // stateful loops keep their state-determined behaviour
+        let acc = wire()
+            .feedback_op(delay(0.0), |i1: f64, i2: f64| i1 + i2)
+            .fixed_point(1e-12, 10);
+        assert_eq!(
+            &[1.0, 3.0, 6.0],
+            acc.transduce([1.0, 2.0, 3.0])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+
+        // y = 2y + x diverges
+        let sm = wire()
+            .feedback_op(scale(2.0), |i1: f64, i2: f64| i1 + i2)
+            .fixed_point(1e-9, 50);
+        assert_eq!(sm.transduce([1.0]).count(), 0);
+    }
+
+    fn make_timer(n: i32) -> impl StateMachine<i32, i32, State = i32> {
+        (
+            |state: i32, _input: i32| (state + 1, state + 1),
+            move |state: i32| state >= n,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_done_policy() {
+        let steps = |policy| {
+            make_timer(2)
+                .parallel(make_timer(4))
+                .done_when(policy)
+                .transduce(0..10)
+                .count()
+        };
+        assert_eq!(
+            [2, 4, 2, 4],
+            [
+                DonePolicy::Any,
+                DonePolicy::All,
+                DonePolicy::First,
+                DonePolicy::Second
+            ]
+            .map(steps)
+        );
+
+        let steps = |policy| {
+            make_timer(4)
+                .cascade(make_timer(2))
+                .done_when(policy)
+                .transduce(0..10)
+                .count()
+        };
+        assert_eq!([2, 4], [DonePolicy::Any, DonePolicy::First].map(steps));
+
+        assert_eq!(
+            4,
+            make_timer(1)
+                .switch(make_timer(3), |i| i == 0)
+                .done_when(DonePolicy::All)
+                .transduce(0..10)
+                .count()
+        );
+        assert_eq!(
+            &[1, 2, 3],
+            make_timer(5)
+                .mux(make_timer(3), |i| i > 100)
+                .done_when(DonePolicy::Second)
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_parallel_n() {
+        let timers = || vec![make_timer(2), make_timer(4), make_timer(3)];
+        assert_eq!(
+            &[vec![1, 1, 1], vec![2, 2, 2]],
+            ParallelN::new(timers())
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        let steps = |policy| {
+            ParallelN::new(timers())
+                .done_when(policy)
+                .transduce(0..10)
+                .count()
+        };
+        assert_eq!(
+            [2, 4, 2, 4],
+            [
+                DonePolicy::Any,
+                DonePolicy::All,
+                DonePolicy::First,
+                DonePolicy::Second
+            ]
+            .map(steps)
+        );
+        assert_eq!(
+            &[vec![0, 0], vec![1, 2], vec![2, 4]],
+            ParallelN::new(vec![delay(0), delay(0)])
+                .cascade(|v: Vec<i32>| vec![v[0], v[1] * 2])
+                .transduce(1..4)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_map_input_output() {
+        let sm = make_acc()
+            .map_input(|s: &str| s.len() as i32)
+            .map_output(|acc| acc * 10);
+        assert_eq!(
+            &[10, 30, 30, 70],
+            sm.transduce(["a", "bb", "", "dddd"])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        // adapters keep the machine's own `done`
+        assert_eq!(
+            3,
+            make_timer(3)
+                .map_input(|b: bool| b as i32)
+                .map_output(|i| i * 2)
+                .transduce([true; 10])
+                .count()
+        );
+        // and stay transparent to feedback look-ahead
+        assert_eq!(
+            &[2, 4, 6, 8],
+            delay(0)
+                .map_output(|i| i + 1)
+                .map_input(|i: i32| i)
+                .feedback()
+                .map_output(|i| i * 2)
+                .transduce(0..4)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut sm = make_acc()
+            .filter(|acc| acc % 2 == 0)
+            .into_state_full_machine();
+        let outputs = [1, 1, 3, 1, 2]
+            .into_iter()
+            .map(|i| sm.step(Some(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, vec![None, Some(2), None, Some(6), Some(8)]);
+
+        // a filtered step does not reach the next machine
+        let mut sm = wire()
+            .filter(|i: &i32| *i > 0)
+            .cascade(make_acc())
+            .into_state_full_machine();
+        let outputs = [1, -5, 2]
+            .into_iter()
+            .map(|i| sm.step(Some(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, vec![Some(1), None, Some(3)]);
+
+        // `transduce` stops at the first filtered step, `transduce_defined`
+        // leaves it out and keeps going
+        let even = || make_acc().filter(|acc| acc % 2 == 0);
+        assert_eq!(even().transduce([1, 1, 3, 1, 2]).count(), 0);
+        assert_eq!(
+            &[2, 6, 8],
+            even()
+                .transduce_defined([1, 1, 3, 1, 2])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut sm = (|i: i32| i * 2)
+            .scan(Vec::new(), |mut acc: Vec<i32>, o| {
+                acc.push(o);
+                acc
+            })
+            .into_state_full_machine();
+        assert_eq!(sm.step(Some(1)), Some(vec![2]));
+        assert_eq!(sm.step(Some(2)), Some(vec![2, 4]));
+        sm.reset();
+        assert_eq!(sm.step(Some(3)), Some(vec![6]));
+
+        assert_eq!(
+            &[1, 2, 6, 24],
+            make_timer(4)
+                .scan(1, |acc, o| acc * o)
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_zip_with() {
+        assert_eq!(
+            &[0, 1, 3, 5],
+            delay(0)
+                .zip_with(delay(0).cascade(delay(0)), |a, b| a + b)
+                .transduce(1..5)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        let steps = |policy| {
+            make_timer(2)
+                .zip_with(make_timer(5), |a, b| (a, b))
+                .done_when(policy)
+                .transduce(0..10)
+                .count()
+        };
+        assert_eq!([2, 5], [DonePolicy::Any, DonePolicy::All].map(steps));
+    }
+
+    #[test]
+    fn test_lib601_primitives() {
+        let run = |sm: &dyn Fn(Vec<i32>) -> Vec<i32>| sm(vec![1, 2, 3]);
+        assert_eq!(run(&|i| gain(3).transduce(i).collect()), [3, 6, 9]);
+        assert_eq!(run(&|i| increment(2).transduce(i).collect()), [3, 4, 5]);
+        assert_eq!(run(&|i| sum().transduce(i).collect()), [1, 3, 6]);
+        assert_eq!(
+            run(&|i| wire::<i32>().cascade(constant(7)).transduce(i).collect()),
+            [7, 7, 7]
+        );
+        assert_eq!(run(&|i| r(0).transduce(i).collect()), [0, 1, 2]);
+        assert_eq!(
+            run(&|i| pure_function(|x: i32| x * x).transduce(i).collect()),
+            [1, 4, 9]
+        );
+        assert_eq!(
+            adder().transduce([(1, 2), (3, -4)]).collect::<Vec<_>>(),
+            [3, -1]
+        );
+        assert_eq!(
+            negation().transduce([true, false]).collect::<Vec<_>>(),
+            [false, true]
+        );
+        assert_eq!(
+            wire::<Vec<i32>>()
+                .cascade(select(1))
+                .transduce([vec![1, 2], vec![3, 4, 5]])
+                .collect::<Vec<_>>(),
+            [2, 4]
+        );
+        assert_eq!(
+            wire::<[bool; 2]>()
+                .cascade(select(0))
+                .cascade(negation())
+                .transduce([[true, false]])
+                .collect::<Vec<_>>(),
+            [false]
+        );
+    }
+
+    #[test]
+    fn test_lib601_composites() {
+        assert_eq!(
+            &[2, 5, 8],
+            feedforward_add(gain(2), r(0))
+                .transduce([1, 2, 3])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        // accumulator, as `sf::gain(1.0).feedback_add(Some(sf::delay()))`
+        assert_eq!(
+            &[1, 3, 6],
+            feedback_add(wire(), r(0))
+                .transduce([1, 2, 3])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            &[1, 1, 2],
+            feedback_subtract(gain(1), r(0))
+                .transduce([1, 2, 3])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        // lib601 counter: the output only depends on the delay's state
+        assert_eq!(
+            &[0, 1, 2, 3],
+            feedback_add(r(0), wire())
+                .transduce([1; 4])
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert!(feedback_add(wire(), gain(2)).check_loop().is_err());
+        assert!(
+            feedback_add(wire::<i32>(), constant(2))
+                .check_loop()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_seq_from_iter() {
+        let expected = [1, 1, 2, 1, 2, 3];
+        assert_eq!(
+            &expected,
+            seq_from_iter((1..=3).map(make_timer))
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            &expected,
+            crate::seq!(make_timer(1), make_timer(2), make_timer(3))
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        // machines that start done are skipped
+        assert_eq!(
+            &[1, 1],
+            seq_from_iter([make_timer(0), make_timer(1), make_timer(0), make_timer(1)])
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            0,
+            seq_from_iter(Vec::<crate::sm_course::Delay<i32>>::new())
+                .transduce(0..10)
+                .count()
+        );
+
+        let boxed = vec![
+            make_timer(2).boxed(),
+            (|i: i32| i * 10).until(|i| i > 3).boxed(),
+            make_timer(1).boxed(),
+        ];
+        assert_eq!(
+            &[1, 2, 20, 30, 40, 1],
+            seq_from_iter(boxed)
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_seq_repeat() {
+        assert_eq!(
+            &[1, 1, 2, 1, 1, 2],
+            seq_repeat([make_timer(1), make_timer(2)], Some(2))
+                .transduce(0..10)
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            9,
+            seq_repeat([make_timer(1), make_timer(2)], None)
+                .transduce(0..9)
+                .count()
+        );
+    }
+
     #[test]
     fn test_switch() {
         assert_eq!(
@@ -0,0 +1,147 @@
+use std::fmt::Display;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::sm::{StateFull, StateFullMachine, StateMachine};
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The machine was not built with `into_snapshot_machine`.
+    Unsupported,
+    Format(serde_json::Error),
+    /// The snapshot parses but the machine cannot step from it, e.g. an
+    /// index past the end of a `SeqN`.
+    Invalid,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Unsupported => write!(f, "machine does not support snapshots"),
+            SnapshotError::Format(err) => write!(f, "invalid snapshot: {err}"),
+            SnapshotError::Invalid => write!(f, "snapshot does not fit the machine"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(value: serde_json::Error) -> Self {
+        SnapshotError::Format(value)
+    }
+}
+
+pub struct Snapshot<In, Out, SM>(pub(crate) StateFull<In, Out, SM>)
+where
+    SM: StateMachine<In, Out>;
+
+impl<In, Out, SM> StateFullMachine<In, Out> for Snapshot<In, Out, SM>
+where
+    SM: StateMachine<In, Out>,
+    SM::State: Serialize + DeserializeOwned,
+{
+    fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    fn step(&mut self, input: Option<In>) -> Option<Out> {
+        self.0.step(input)
+    }
+
+    fn is_done(&self) -> bool {
+        self.0.is_done()
+    }
+
+    fn snapshot(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string(&self.0.0)?)
+    }
+
+    fn restore(&mut self, snapshot: &str) -> Result<(), SnapshotError> {
+        let state = serde_json::from_str(snapshot)?;
+        if !self.0.1.validate(&state) {
+            return Err(SnapshotError::Invalid);
+        }
+        self.0.0 = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sm::{StateFullMachine, StateMachine, seq_from_iter},
+        sm_course::{delay, wire},
+        snapshot::SnapshotError,
+    };
+    use std::ops::Add;
+
+    fn make_counter() -> impl StateMachine<i32, i32, State = i32> {
+        (
+            |state: i32, input: i32| (state + input, state + input),
+            |state: i32| state >= 3,
+            0,
+        )
+    }
+
+    fn assert_resumes<SM: StateFullMachine<i32, i32>>(mut a: SM, mut b: SM, inputs: &[i32]) {
+        let (head, tail) = inputs.split_at(inputs.len() / 2);
+        for i in head {
+            a.step(Some(*i));
+        }
+        b.restore(&a.snapshot().unwrap()).unwrap();
+        for i in tail {
+            assert_eq!(a.step(Some(*i)), b.step(Some(*i)));
+            assert_eq!(a.is_done(), b.is_done());
+        }
+    }
+
+    #[test]
+    fn test_restore_combinators() {
+        let make = || {
+            delay(1)
+                .parallel(delay(1).cascade(delay(0)))
+                .cascade(|(i1, i2)| i1 + i2)
+                .feedback()
+                .switch(wire().feedback_op(delay(0), i32::add), |i| i % 3 == 0)
+                .mux(
+                    (|(i1, i2): (i32, i32)| i1 + i2)
+                        .cascade(delay(1))
+                        .feedback2(),
+                    |i| i > 4,
+                )
+                .into_snapshot_machine()
+        };
+        assert_resumes(make(), make(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        let make = || {
+            make_counter()
+                .repeat(Some(2))
+                .seq(make_counter().until(|i| i > 5))
+                .seq(make_counter().repeat_until(|i| i == 0))
+                .seq(make_counter().r#if(make_counter(), |i| i > 0))
+                .into_snapshot_machine()
+        };
+        assert_resumes(make(), make(), &[1, 1, 1, 2, 2, 2, 6, 1, 1, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_snapshot() {
+        let mut sm = delay(0).into_snapshot_machine();
+        assert!(matches!(
+            sm.restore("[1, 2]"),
+            Err(SnapshotError::Format(_))
+        ));
+        assert_eq!(sm.step(Some(5)), Some(0));
+        assert_eq!(sm.snapshot().unwrap(), "5");
+
+        let mut seq = seq_from_iter([delay(0), delay(1)]).into_snapshot_machine();
+        assert!(matches!(seq.restore("[2, 7]"), Err(SnapshotError::Invalid)));
+        assert_eq!(seq.snapshot().unwrap(), "[0,0]");
+        seq.restore("[1, 7]").unwrap();
+        assert_eq!(seq.step(Some(3)), Some(7));
+
+        let plain = delay(0).into_state_full_machine();
+        assert!(matches!(plain.snapshot(), Err(SnapshotError::Unsupported)));
+    }
+}
@@ -1,62 +1,90 @@
-use crate::io::{Point, Pose};
+use crate::{
+    io::{Point, Pose, SensorInput},
+    robot::{PIONEER, RobotModel},
+};
 use std::f64::consts::PI;
 
-static SONAR_POSES: [Pose; 8] = [
-    Pose {
-        pos: Point::new(0.08, 0.134),
-        theta: PI / 2.0,
-    },
-    Pose {
-        pos: Point::new(0.122, 0.118),
-        theta: 5.0 * PI / 18.0,
-    },
-    Pose {
-        pos: Point::new(0.156, 0.077),
-        theta: PI / 6.0,
-    },
-    Pose {
-        pos: Point::new(0.174, 0.0266),
-        theta: PI / 18.0,
-    },
-    Pose {
-        pos: Point::new(0.174, -0.0266),
-        theta: -PI / 18.0,
-    },
-    Pose {
-        pos: Point::new(0.156, -0.077),
-        theta: -PI / 6.0,
-    },
-    Pose {
-        pos: Point::new(0.122, -0.118),
-        theta: -5.0 * PI / 18.0,
-    },
-    Pose {
-        pos: Point::new(0.08, -0.134),
-        theta: -PI / 2.0,
-    },
-];
-
-const SONAR_MAX: f64 = 1.5;
-
+/// lib601's `getDistanceRight` for the Pioneer.
 pub fn get_distance_right(sonars: &[f64; 8]) -> f64 {
-    let mut hits = [None; 8];
-    for (hit, (spose, d)) in hits.iter_mut().zip(SONAR_POSES.iter().zip(sonars.iter())) {
-        *hit = (*d < SONAR_MAX).then(|| spose.pos + Point::from_polar(*d, spose.theta));
-    }
-    distance_right(hits[6], hits[7])
+    distance_right_and_angle(&PIONEER, sonars).0
 }
 
-fn distance_right(h0: Option<Point>, h1: Option<Point>) -> f64 {
-    match (h0, h1) {
+/// lib601's `getDistanceRightAndAngle` for the Pioneer.
+pub fn get_distance_right_and_angle(sonars: &[f64; 8]) -> (f64, Option<f64>) {
+    distance_right_and_angle(&PIONEER, sonars)
+}
+
+/// Distance from the robot center to the wall on its right, and the angle
+/// of the robot heading relative to that wall, positive when heading away
+/// from it. The angle needs hits on both of the model's right sonars and is
+/// `None` when only one of them sees the wall. A right sonar without a
+/// reading counts as seeing nothing.
+pub fn distance_right_and_angle(model: &RobotModel, sonars: &[f64]) -> (f64, Option<f64>) {
+    let hits = sonar_hits(model, sonars);
+    let (front, back) = model.right_sonars;
+    match (
+        hits.get(front).copied().flatten(),
+        hits.get(back).copied().flatten(),
+    ) {
         (Some(h0), Some(h1)) => {
-            let (_, lined) = line(h0, h1);
-            lined.abs()
+            let (normal, lined) = line(h0, h1);
+            (lined.abs(), Some(normal.x.atan2(normal.y)))
         }
-        (Some(h), None) | (None, Some(h)) => h.distance_to_orig(),
-        (None, None) => SONAR_MAX,
+        (Some(h), None) | (None, Some(h)) => (h.distance_to_orig(), None),
+        (None, None) => (model.sonar_max, None),
     }
 }
 
+/// Robot-frame points where each sonar beam hit something, `None` for
+/// readings at the model's `sonar_max`.
+pub fn sonar_hits(model: &RobotModel, sonars: &[f64]) -> Vec<Option<Point>> {
+    model
+        .sonar_poses
+        .iter()
+        .zip(sonars)
+        .map(|(spose, d)| {
+            (*d < model.sonar_max).then(|| spose.pos + Point::from_polar(*d, spose.theta))
+        })
+        .collect()
+}
+
+/// Where a sonar beam ended, in the world frame.
+#[derive(Debug, Clone, Copy)]
+pub struct WorldHit {
+    /// The sonar, where the beam started.
+    pub origin: Point,
+    /// The end of the beam: the obstacle when `in_range`, otherwise the
+    /// point at `sonar_max` along the beam.
+    pub point: Point,
+    /// Whether the beam hit something within range.
+    pub in_range: bool,
+}
+
+/// `world_hits` for the Pioneer's `SensorInput`.
+pub fn get_world_hits(input: &SensorInput) -> Vec<WorldHit> {
+    world_hits(&PIONEER, &input.odometry, &input.sonars)
+}
+
+/// Places each sonar reading in the world frame, using `odometry` as the
+/// robot pose.
+pub fn world_hits(model: &RobotModel, odometry: &Pose, sonars: &[f64]) -> Vec<WorldHit> {
+    model
+        .sonar_poses
+        .iter()
+        .zip(sonars)
+        .map(|(spose, d)| {
+            let in_range = *d < model.sonar_max;
+            let d = d.min(model.sonar_max);
+            let sonar = odometry.compose(spose);
+            WorldHit {
+                origin: sonar.pos,
+                point: sonar.transform_point(Point::new(d, 0.0)),
+                in_range,
+            }
+        })
+        .collect()
+}
+
 fn line(h0: Point, h1: Point) -> (Point, f64) {
     let delta = h1 - h0;
     let mag = delta.distance_to_orig();
@@ -66,12 +94,328 @@ fn line(h0: Point, h1: Point) -> (Point, f64) {
     (Point::new(nx, ny), d)
 }
 
+/// How far a hit may lie from a wall's fitted line and still belong to it.
+pub const WALL_FIT_TOL: f64 = 0.05;
+
+/// A straight wall seen by neighbouring sonars, in the robot frame.
+#[derive(Debug, Clone)]
+pub struct WallFeature {
+    /// Unit normal pointing from the robot center towards the wall.
+    pub normal: Point,
+    /// Distance from the robot center to the wall's line.
+    pub offset: f64,
+    /// Direction of the wall relative to the robot heading, in
+    /// `(-PI / 2, PI / 2]`.
+    pub angle: f64,
+    /// Root mean square distance of the fitted hits from the line.
+    pub residual: f64,
+    /// Sonars whose hits were fitted, left to right.
+    pub sonars: Vec<usize>,
+}
+
+impl WallFeature {
+    fn fit(hits: &[Option<Point>], sonars: Vec<usize>) -> Self {
+        let points = sonars.iter().filter_map(|&i| hits[i]).collect::<Vec<_>>();
+        let (normal, offset) = fit_line(&points);
+        let residual = (points
+            .iter()
+            .map(|p| (normal.x * p.x + normal.y * p.y - offset).powi(2))
+            .sum::<f64>()
+            / points.len() as f64)
+            .sqrt();
+        let mut angle = (-normal.x).atan2(normal.y);
+        if angle > PI / 2.0 {
+            angle -= PI;
+        } else if angle <= -PI / 2.0 {
+            angle += PI;
+        }
+        Self {
+            normal,
+            offset,
+            angle,
+            residual,
+            sonars,
+        }
+    }
+
+    /// Where this wall's line meets `other`'s, `None` if they are parallel.
+    pub fn corner_with(&self, other: &WallFeature) -> Option<Point> {
+        let (n1, n2) = (self.normal, other.normal);
+        let det = n1.x * n2.y - n1.y * n2.x;
+        if det.abs() < 1e-6 {
+            return None;
+        }
+        Some(Point::new(
+            (self.offset * n2.y - other.offset * n1.y) / det,
+            (other.offset * n1.x - self.offset * n2.x) / det,
+        ))
+    }
+}
+
+/// Segments the hits of all the model's sonars, left to right, into walls.
+///
+/// A hit joins the current wall while it lies within `WALL_FIT_TOL` of the
+/// least-squares line through the wall's hits so far. A single stray hit or
+/// missing reading (a glancing beam, a doorway) is skipped if the next hit
+/// is back on the line, as is a stray second hit. Walls need at least two
+/// hits.
+pub fn extract_walls(model: &RobotModel, sonars: &[f64]) -> Vec<WallFeature> {
+    let hits = sonar_hits(model, sonars);
+    let mut walls = vec![];
+    let mut start = 0;
+    loop {
+        let mut members = grow_wall(&hits, start);
+        if let [_, second] = members[..] {
+            // two hits always fit a line: make sure the second is no stray
+            let mut without = hits.clone();
+            without[second] = None;
+            let retry = grow_wall(&without, start);
+            if retry.len() > 2 {
+                members = retry;
+            }
+        }
+        let Some(&last) = members.last() else {
+            break;
+        };
+        start = last + 1;
+        if members.len() >= 2 {
+            walls.push(WallFeature::fit(&hits, members));
+        }
+    }
+    walls
+}
+
+fn grow_wall(hits: &[Option<Point>], start: usize) -> Vec<usize> {
+    let mut members: Vec<usize> = vec![];
+    let mut strays = 0;
+    for (i, hit) in hits.iter().enumerate().skip(start) {
+        let on_wall = hit.is_some_and(|hit| {
+            members.len() < 2 || {
+                let points = members.iter().filter_map(|&i| hits[i]).collect::<Vec<_>>();
+                let (normal, offset) = fit_line(&points);
+                (normal.x * hit.x + normal.y * hit.y - offset).abs() < WALL_FIT_TOL
+            }
+        });
+        if on_wall {
+            members.push(i);
+            strays = 0;
+        } else if !members.is_empty() {
+            strays += 1;
+            if strays > 1 {
+                break;
+            }
+        }
+    }
+    members
+}
+
+/// Total least squares line through `points`, as its unit normal pointing
+/// away from the origin and its distance from the origin.
+fn fit_line(points: &[Point]) -> (Point, f64) {
+    let n = points.len() as f64;
+    let sum = points.iter().fold(Point::new(0.0, 0.0), |acc, p| acc + *p);
+    let c = Point::new(sum.x / n, sum.y / n);
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for p in points {
+        let d = *p - c;
+        sxx += d.x * d.x;
+        syy += d.y * d.y;
+        sxy += d.x * d.y;
+    }
+    let phi = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let normal = Point::new(-phi.sin(), phi.cos());
+    let offset = normal.x * c.x + normal.y * c.y;
+    if offset < 0.0 {
+        (Point::new(-normal.x, -normal.y), -offset)
+    } else {
+        (normal, offset)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        io::Pose,
+        sim::{Simulator, Wall},
+    };
 
     const EPSILON: f64 = 10e-9;
 
+    #[test]
+    fn test_angle_of_parallel_and_tilted_walls() {
+        let wall = Wall::new(Point::new(-3.0, -0.5), Point::new(3.0, -0.5));
+        for theta in [0.0, 0.2, -0.2] {
+            let sim = Simulator::new(
+                Pose {
+                    pos: Point::new(0.0, 0.0),
+                    theta,
+                },
+                vec![wall],
+            );
+            let (distance, angle) = get_distance_right_and_angle(&sim.sensor_input().sonars);
+            assert!(
+                (distance - 0.5).abs() < EPSILON,
+                "theta {theta}: {distance}"
+            );
+            let angle = angle.expect("both right sonars see the wall");
+            assert!((angle - theta).abs() < EPSILON, "theta {theta}: {angle}");
+        }
+    }
+
+    #[test]
+    fn test_angle_needs_both_hits() {
+        let (distance, angle) =
+            get_distance_right_and_angle(&[1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 0.4]);
+        assert!((distance - 0.5399592577).abs() < EPSILON);
+        assert_eq!(angle, None);
+        assert_eq!(get_distance_right_and_angle(&[1.5; 8]), (1.5, None));
+        // sensor 6 closer than 7: heading towards the wall
+        let (_, angle) = get_distance_right_and_angle(&[1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 0.4, 0.6]);
+        assert!(angle.unwrap() < 0.0);
+    }
+
+    fn sonars_at(theta: f64, walls: Vec<Wall>) -> [f64; 8] {
+        let pose = Pose {
+            pos: Point::new(0.0, 0.0),
+            theta,
+        };
+        Simulator::new(pose, walls).sensor_input().sonars
+    }
+
+    #[test]
+    fn test_extract_corridor_walls() {
+        let sonars = sonars_at(
+            0.1,
+            vec![
+                Wall::new(Point::new(-3.0, 0.5), Point::new(3.0, 0.5)),
+                Wall::new(Point::new(-3.0, -0.5), Point::new(3.0, -0.5)),
+            ],
+        );
+        let walls = extract_walls(&RobotModel::default(), &sonars);
+        assert_eq!(walls.len(), 2, "{walls:?}");
+        let (left, right) = (&walls[0], &walls[1]);
+        assert_eq!(left.sonars, vec![0, 1, 2]);
+        assert_eq!(right.sonars, vec![5, 6, 7]);
+        for wall in &walls {
+            assert!((wall.offset - 0.5).abs() < EPSILON, "{wall:?}");
+            assert!((wall.angle + 0.1).abs() < EPSILON, "{wall:?}");
+            assert!(wall.residual < EPSILON);
+        }
+        assert!(left.normal.y > 0.0 && right.normal.y < 0.0);
+        assert!(left.corner_with(right).is_none());
+    }
+
+    #[test]
+    fn test_extract_corner() {
+        let sonars = sonars_at(
+            0.0,
+            vec![
+                Wall::new(Point::new(0.6, -3.0), Point::new(0.6, 3.0)),
+                Wall::new(Point::new(-3.0, -0.5), Point::new(3.0, -0.5)),
+            ],
+        );
+        let walls = extract_walls(&RobotModel::default(), &sonars);
+        assert_eq!(walls.len(), 2, "{walls:?}");
+        assert_eq!(walls[0].sonars, vec![1, 2, 3, 4, 5]);
+        assert_eq!(walls[1].sonars, vec![6, 7]);
+        assert!((walls[0].offset - 0.6).abs() < EPSILON);
+        assert!((walls[0].angle - PI / 2.0).abs() < EPSILON);
+        let corner = walls[0].corner_with(&walls[1]).unwrap();
+        assert!(corner.is_near(Point::new(0.6, -0.5), EPSILON), "{corner:?}");
+    }
+
+    #[test]
+    fn test_extract_rejects_stray_hit() {
+        let wall = Wall::new(Point::new(-3.0, -0.3), Point::new(3.0, -0.3));
+        let sonars = sonars_at(-0.3, vec![wall]);
+        let clean = extract_walls(&RobotModel::default(), &sonars);
+        assert_eq!(clean.len(), 1);
+        assert_eq!(clean[0].sonars, vec![4, 5, 6, 7]);
+
+        // a glancing beam reads long, a doorway reads nothing
+        for reading in [1.2, 1.5] {
+            let mut sonars = sonars;
+            sonars[5] = reading;
+            let walls = extract_walls(&RobotModel::default(), &sonars);
+            assert_eq!(walls.len(), 1, "{walls:?}");
+            assert_eq!(walls[0].sonars, vec![4, 6, 7]);
+            assert!((walls[0].offset - 0.3).abs() < EPSILON);
+            assert!((walls[0].angle - 0.3).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_other_sonar_ring() {
+        let model = RobotModel::parse(
+            "radius(0.3)\nsonarMax(4)\n\
+             sonar((0.1, -0.2), -45)\nsonar((0, -0.3), -90)\nsonar((-0.1, -0.2), -135)\n\
+             rightSonars(0, 2)\n",
+        )
+        .unwrap();
+        let wall = Wall::new(Point::new(-5.0, -2.0), Point::new(5.0, -2.0));
+        let pose = Pose {
+            pos: Point::new(0.0, 0.0),
+            theta: 0.1,
+        };
+        let sonars = Simulator::new(pose, vec![wall])
+            .with_model(model.clone())
+            .sonars();
+        assert_eq!(sonars.len(), 3);
+        let (distance, angle) = distance_right_and_angle(&model, &sonars);
+        assert!((distance - 2.0).abs() < EPSILON, "{distance}");
+        assert!((angle.unwrap() - 0.1).abs() < EPSILON);
+        let walls = extract_walls(&model, &sonars);
+        assert_eq!(walls.len(), 1);
+        assert_eq!(walls[0].sonars, vec![0, 1, 2]);
+        assert!((walls[0].offset - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_world_hits_lie_on_walls() {
+        let walls = vec![
+            Wall::new(Point::new(0.0, 0.0), Point::new(4.0, 0.0)),
+            Wall::new(Point::new(3.0, 0.0), Point::new(3.0, 4.0)),
+        ];
+        let odometry = Pose {
+            pos: Point::new(2.0, 0.6),
+            theta: -0.4,
+        };
+        let input = Simulator::new(odometry.clone(), walls).sensor_input();
+        let hits = get_world_hits(&input);
+        assert_eq!(hits.len(), 8);
+        for (hit, d) in hits.iter().zip(input.sonars) {
+            assert!((hit.origin.distance_to(hit.point) - d).abs() < EPSILON);
+            if hit.in_range {
+                let Point { x, y } = hit.point;
+                assert!(y.abs() < EPSILON || (x - 3.0).abs() < EPSILON, "{hit:?}");
+            }
+        }
+        assert!(!hits[0].in_range);
+        assert!(hits[7].in_range);
+        assert!(hits[0].origin.distance_to(odometry.pos) < 0.2);
+    }
+
+    #[test]
+    fn test_right_sonars_without_readings() {
+        let mut model = PIONEER.clone();
+        model
+            .sonar_poses
+            .extend(PIONEER.sonar_poses[6..].iter().cloned());
+        model.right_sonars = (8, 9);
+        let sonar = [1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 0.5, 0.5];
+        assert_eq!(
+            distance_right_and_angle(&model, &sonar),
+            (model.sonar_max, None)
+        );
+        assert_eq!(
+            distance_right_and_angle(&PIONEER, &sonar[..6]),
+            (PIONEER.sonar_max, None)
+        );
+        let (distance, angle) = distance_right_and_angle(&PIONEER, &sonar[..7]);
+        assert!(distance < 1.0 && angle.is_none());
+    }
+
     #[test]
     fn test_parallel_wall_on_right_close() {
         // Robot parallel to wall 0.5m away on right
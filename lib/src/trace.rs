@@ -0,0 +1,331 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use crate::sm::StateMachine;
+
+#[derive(Debug, Clone)]
+pub struct TraceRecord {
+    pub machine: String,
+    pub step: usize,
+    pub input: String,
+    pub output: String,
+    pub state: String,
+}
+
+impl Display for TraceRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} step {}: In: {} Out: {} Next State: {}",
+            self.machine, self.step, self.input, self.output, self.state
+        )
+    }
+}
+
+#[derive(Default)]
+struct Log {
+    records: Vec<TraceRecord>,
+    steps: HashMap<String, usize>,
+    probes: HashMap<String, Vec<f64>>,
+    verbose: bool,
+}
+
+/// Shared sink for the machines wrapped by `StateMachine::traced` and
+/// `StateMachine::probe`. Clones share the same log.
+#[derive(Clone, Default)]
+pub struct Tracer(Rc<RefCell<Log>>);
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also print every record to stderr as it is made, like lib601's
+    /// `transduce(verbose=True)`.
+    pub fn set_verbose(&self, verbose: bool) {
+        self.0.borrow_mut().verbose = verbose;
+    }
+
+    pub fn records(&self) -> Vec<TraceRecord> {
+        self.0.borrow().records.clone()
+    }
+
+    pub fn records_of(&self, machine: &str) -> Vec<TraceRecord> {
+        self.0
+            .borrow()
+            .records
+            .iter()
+            .filter(|r| r.machine == machine)
+            .cloned()
+            .collect()
+    }
+
+    pub fn probe(&self, name: &str) -> Vec<f64> {
+        self.0
+            .borrow()
+            .probes
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        let mut log = self.0.borrow_mut();
+        log.records.clear();
+        log.steps.clear();
+        log.probes.clear();
+    }
+
+    fn record(&self, machine: &str, input: String, output: String, state: String) {
+        let mut log = self.0.borrow_mut();
+        let step = log.steps.entry(machine.to_string()).or_default();
+        let record = TraceRecord {
+            machine: machine.to_string(),
+            step: *step,
+            input,
+            output,
+            state,
+        };
+        *step += 1;
+        if log.verbose {
+            eprintln!("{record}");
+        }
+        log.records.push(record);
+    }
+
+    fn sample(&self, name: &str, value: f64) {
+        self.0
+            .borrow_mut()
+            .probes
+            .entry(name.to_string())
+            .or_default()
+            .push(value);
+    }
+}
+
+thread_local! {
+    static LOOK_AHEAD: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `f` as a look-ahead: combinators such as `Feedback` or
+/// `fixed_point` evaluate their machines to see what they would output
+/// before stepping them for real. Traced and probed machines record nothing
+/// while `f` runs, nor on calls without an input.
+pub fn look_ahead<T>(f: impl FnOnce() -> T) -> T {
+    struct Depth;
+    impl Drop for Depth {
+        fn drop(&mut self) {
+            LOOK_AHEAD.with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+    LOOK_AHEAD.with(|depth| depth.set(depth.get() + 1));
+    let _depth = Depth;
+    f()
+}
+
+fn is_looking_ahead() -> bool {
+    LOOK_AHEAD.with(|depth| depth.get() > 0)
+}
+
+pub struct Traced<SM> {
+    pub(crate) machine: SM,
+    pub(crate) name: String,
+    pub(crate) tracer: Tracer,
+}
+
+impl<In, Out, SM> StateMachine<In, Out> for Traced<SM>
+where
+    In: Debug,
+    Out: Debug,
+    SM: StateMachine<In, Out>,
+    SM::State: Debug,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let input = match input {
+            Some(input) if !is_looking_ahead() => input,
+            input => return self.machine.next_values(state, input),
+        };
+        let input_repr = format!("{input:?}");
+        let (new_state, output) = self.machine.next_values(state, Some(input));
+        self.tracer.record(
+            &self.name,
+            input_repr,
+            format!("{output:?}"),
+            format!("{new_state:?}"),
+        );
+        (new_state, output)
+    }
+}
+
+/// Samples `f` of the machine's output at each step, or NaN when the step
+/// has no output, so that sample `k` is always from step `k`.
+pub struct Probe<SM, F> {
+    pub(crate) machine: SM,
+    pub(crate) name: String,
+    pub(crate) tracer: Tracer,
+    pub(crate) f: F,
+}
+
+impl<In, Out, SM, F> StateMachine<In, Out> for Probe<SM, F>
+where
+    SM: StateMachine<In, Out>,
+    F: Fn(&Out) -> f64,
+{
+    type State = SM::State;
+
+    fn start_state(&self) -> Self::State {
+        self.machine.start_state()
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(state)
+    }
+
+    fn next_values(&self, state: Self::State, input: Option<In>) -> (Self::State, Option<Out>) {
+        let stepped = input.is_some() && !is_looking_ahead();
+        let (new_state, output) = self.machine.next_values(state, input);
+        if stepped {
+            let value = output.as_ref().map_or(f64::NAN, &self.f);
+            self.tracer.sample(&self.name, value);
+        }
+        (new_state, output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sm::StateMachine,
+        sm_course::{delay, wire},
+        trace::Tracer,
+    };
+    use std::ops::Add;
+
+    #[test]
+    fn test_trace_cascade() {
+        let tracer = Tracer::new();
+        let acc = (
+            |state: i32, input: i32| {
+                let acc = state + input;
+                (acc, acc)
+            },
+            0,
+        );
+        let sm = (|i: i32| i * 2)
+            .traced("double", &tracer)
+            .cascade(acc.traced("acc", &tracer));
+        assert_eq!(
+            &[2, 6, 12],
+            sm.transduce([1, 2, 3]).collect::<Vec<_>>().as_slice()
+        );
+
+        let records = tracer.records_of("acc");
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            (
+                records[1].step,
+                records[1].input.as_str(),
+                records[1].output.as_str(),
+                records[1].state.as_str()
+            ),
+            (1, "4", "Some(6)", "6")
+        );
+        assert_eq!(tracer.records().len(), 6);
+        assert_eq!(
+            tracer.records()[0].to_string(),
+            "double step 0: In: 1 Out: Some(2) Next State: ()"
+        );
+    }
+
+    #[test]
+    fn test_probe_inside_feedback() {
+        let tracer = Tracer::new();
+        let acc = wire()
+            .probe("sum", &tracer, |o: &i32| *o as f64)
+            .feedback_op(delay(0), i32::add);
+        assert_eq!(
+            &[1, 3, 6, 10],
+            acc.transduce([1, 2, 3, 4]).collect::<Vec<_>>().as_slice()
+        );
+        // the look-ahead evaluations made by `FeedbackOp` are not sampled
+        assert_eq!(tracer.probe("sum"), vec![1.0, 3.0, 6.0, 10.0]);
+        assert!(tracer.probe("missing").is_empty());
+
+        tracer.clear();
+        assert!(tracer.probe("sum").is_empty());
+    }
+
+    #[test]
+    fn test_probe_undefined_steps() {
+        let tracer = Tracer::new();
+        let sm = wire()
+            .filter(|x: &i32| x % 2 == 1)
+            .probe("odd", &tracer, |o: &i32| *o as f64);
+        assert_eq!(
+            sm.transduce_defined([1, 2, 3]).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        let samples = tracer.probe("odd");
+        assert_eq!(samples.len(), 3);
+        assert_eq!((samples[0], samples[2]), (1.0, 3.0));
+        assert!(samples[1].is_nan());
+    }
+
+    #[test]
+    fn test_trace_feedback_path() {
+        let tracer = Tracer::new();
+        let acc = wire().feedback_op(delay(0).traced("delay", &tracer), i32::add);
+        assert_eq!(
+            &[1, 3, 6],
+            acc.transduce([1, 2, 3]).collect::<Vec<_>>().as_slice()
+        );
+        // `FeedbackOp` looks ahead at the delay before stepping it
+        let records = tracer.records_of("delay");
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| (r.step, r.input.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, "1"), (1, "3"), (2, "6")]
+        );
+    }
+
+    #[test]
+    fn test_trace_fixed_point() {
+        let tracer = Tracer::new();
+        let sm = (|y: f64| y.cos())
+            .probe("cos", &tracer, |y: &f64| *y)
+            .traced("loop", &tracer)
+            .feedback()
+            .fixed_point(1e-12, 200);
+        let outputs = sm.transduce([0.0; 3]).collect::<Vec<_>>();
+        // the solver's guesses are not recorded, only the solution
+        assert_eq!(tracer.probe("cos").len(), 3);
+        assert_eq!(tracer.records_of("loop").len(), 3);
+        for (sample, output) in tracer.probe("cos").into_iter().zip(outputs) {
+            assert!((sample - output.cos()).abs() < 1e-9);
+        }
+    }
+}
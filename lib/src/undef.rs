@@ -0,0 +1,240 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::sm::{DonePolicy, StateMachine};
+
+// Machine outputs of type `Option<T>` use `None` as lib601's 'undefined'
+// value: a sonar that saw nothing, an angle that could not be computed.
+// The helpers below propagate it instead of panicking.
+
+pub fn safe_add<T: Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    a.zip(b).map(|(a, b)| a + b)
+}
+
+pub fn safe_sub<T: Sub<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    a.zip(b).map(|(a, b)| a - b)
+}
+
+pub fn safe_mul<T: Mul<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    a.zip(b).map(|(a, b)| a * b)
+}
+
+/// Multiplies defined inputs by `k`.
+pub fn safe_gain<T: Mul<Output = T> + Clone + 'static>(
+    k: T,
+) -> impl StateMachine<Option<T>, Option<T>, State = ()> {
+    move |input: Option<T>| input.map(|input| k.clone() * input)
+}
+
+/// Adds a pair of inputs, undefined if either is.
+pub fn safe_adder<T: Add<Output = T>>()
+-> impl StateMachine<(Option<T>, Option<T>), Option<T>, State = ()> {
+    |(a, b)| safe_add(a, b)
+}
+
+/// What `OnUndefined` outputs in place of an undefined value.
+#[derive(Debug, Clone)]
+pub enum UndefinedPolicy<T> {
+    /// Leave it undefined.
+    Propagate,
+    /// Substitute a fixed value.
+    Default(T),
+    /// Repeat the last defined value, if there was one.
+    Hold,
+}
+
+pub struct OnUndefined<SM, T> {
+    pub(crate) machine: SM,
+    pub(crate) policy: UndefinedPolicy<T>,
+}
+
+impl<In, T, SM> StateMachine<In, Option<T>> for OnUndefined<SM, T>
+where
+    T: Clone,
+    SM: StateMachine<In, Option<T>>,
+{
+    type State = (SM::State, Option<T>);
+
+    fn start_state(&self) -> Self::State {
+        (self.machine.start_state(), None)
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine.done(state.0)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine.validate(&state.0)
+    }
+
+    fn next_values(
+        &self,
+        (state, last): Self::State,
+        input: Option<In>,
+    ) -> (Self::State, Option<Option<T>>) {
+        let (new_state, output) = self.machine.next_values(state, input);
+        let Some(output) = output else {
+            return ((new_state, last), None);
+        };
+        let output = match (output, &self.policy) {
+            (Some(value), _) => Some(value),
+            (None, UndefinedPolicy::Propagate) => None,
+            (None, UndefinedPolicy::Default(value)) => Some(value.clone()),
+            (None, UndefinedPolicy::Hold) => last.clone(),
+        };
+        let last = output.clone().or(last);
+        ((new_state, last), Some(output))
+    }
+}
+
+/// Runs two machines on the same input and adds their outputs, undefined if
+/// either output is.
+pub struct ParallelAdd<SM1, SM2> {
+    pub(crate) machine1: SM1,
+    pub(crate) machine2: SM2,
+}
+
+impl<In, T, SM1, SM2> StateMachine<In, Option<T>> for ParallelAdd<SM1, SM2>
+where
+    In: Clone,
+    T: Add<Output = T>,
+    SM1: StateMachine<In, Option<T>>,
+    SM2: StateMachine<In, Option<T>>,
+{
+    type State = (SM1::State, SM2::State);
+
+    fn start_state(&self) -> Self::State {
+        (self.machine1.start_state(), self.machine2.start_state())
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.machine1.done(state.0) || self.machine2.done(state.1)
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine1.validate(&state.0) && self.machine2.validate(&state.1)
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<In>,
+    ) -> (Self::State, Option<Option<T>>) {
+        let (new_state1, output1) = self.machine1.next_values(state.0, input.clone());
+        let (new_state2, output2) = self.machine2.next_values(state.1, input);
+        (
+            (new_state1, new_state2),
+            output1.zip(output2).map(|(o1, o2)| safe_add(o1, o2)),
+        )
+    }
+}
+
+/// Runs two machines on the same input and pairs their outputs, keeping
+/// each one's undefinedness; the pair itself is always defined.
+pub struct ParallelOpt<SM1, SM2> {
+    pub(crate) machine1: SM1,
+    pub(crate) machine2: SM2,
+    pub(crate) policy: DonePolicy,
+}
+
+impl<SM1, SM2> ParallelOpt<SM1, SM2> {
+    pub fn done_when(self, policy: DonePolicy) -> Self {
+        Self { policy, ..self }
+    }
+}
+
+impl<In, Out1, Out2, SM1, SM2> StateMachine<In, (Option<Out1>, Option<Out2>)>
+    for ParallelOpt<SM1, SM2>
+where
+    In: Clone,
+    SM1: StateMachine<In, Out1>,
+    SM2: StateMachine<In, Out2>,
+{
+    type State = (SM1::State, SM2::State);
+
+    fn start_state(&self) -> Self::State {
+        (self.machine1.start_state(), self.machine2.start_state())
+    }
+
+    fn done(&self, state: Self::State) -> bool {
+        self.policy.done(
+            || self.machine1.done(state.0),
+            || self.machine2.done(state.1),
+        )
+    }
+
+    fn validate(&self, state: &Self::State) -> bool {
+        self.machine1.validate(&state.0) && self.machine2.validate(&state.1)
+    }
+
+    fn next_values(
+        &self,
+        state: Self::State,
+        input: Option<In>,
+    ) -> (Self::State, Option<(Option<Out1>, Option<Out2>)>) {
+        let (new_state1, output1) = self.machine1.next_values(state.0, input.clone());
+        let (new_state2, output2) = self.machine2.next_values(state.1, input);
+        ((new_state1, new_state2), Some((output1, output2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sm::StateMachine,
+        sm_course::{delay, wire},
+        undef::{UndefinedPolicy, safe_add, safe_adder, safe_gain, safe_mul, safe_sub},
+    };
+
+    #[test]
+    fn test_safe_ops() {
+        assert_eq!(safe_add(Some(1), Some(2)), Some(3));
+        assert_eq!(safe_sub(Some(1), None), None);
+        assert_eq!(safe_mul(None::<f64>, Some(2.0)), None);
+        assert_eq!(safe_mul(Some(3.0), Some(2.0)), Some(6.0));
+    }
+
+    #[test]
+    fn test_sensor_dropout() {
+        let readings = [Some(1.0), None, Some(3.0), None, None];
+        let out = |policy| {
+            safe_gain(2.0)
+                .on_undefined(policy)
+                .transduce(readings)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            out(UndefinedPolicy::Propagate),
+            vec![Some(2.0), None, Some(6.0), None, None]
+        );
+        assert_eq!(
+            out(UndefinedPolicy::Default(0.0)),
+            vec![Some(2.0), Some(0.0), Some(6.0), Some(0.0), Some(0.0)]
+        );
+        assert_eq!(
+            out(UndefinedPolicy::Hold),
+            vec![Some(2.0), Some(2.0), Some(6.0), Some(6.0), Some(6.0)]
+        );
+
+        let sum = safe_gain(1)
+            .parallel_add(delay(Some(0)))
+            .cascade(safe_gain(10));
+        assert_eq!(
+            sum.transduce([Some(1), None, Some(2), Some(3)])
+                .collect::<Vec<_>>(),
+            vec![Some(10), None, None, Some(50)]
+        );
+
+        let pair = wire().filter(|x: &i32| *x > 0).parallel_opt(|x: i32| -x);
+        assert_eq!(
+            pair.transduce([1, -2, 3]).collect::<Vec<_>>(),
+            vec![(Some(1), Some(-1)), (None, Some(2)), (Some(3), Some(-3))]
+        );
+
+        assert_eq!(
+            safe_adder()
+                .transduce([(Some(1), Some(2)), (None, Some(2))])
+                .collect::<Vec<_>>(),
+            vec![Some(3), None]
+        );
+    }
+}
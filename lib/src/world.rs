@@ -0,0 +1,431 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    io::{Point, Pose},
+    sim::Wall,
+};
+
+/// A soar world: the `[0, bounds.x] x [0, bounds.y]` arena, its walls and
+/// where the robot starts.
+#[derive(Debug, Clone)]
+pub struct World {
+    pub bounds: Point,
+    pub walls: Vec<Wall>,
+    pub initial_pose: Pose,
+}
+
+impl World {
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut bounds = None;
+        let mut walls = Vec::new();
+        let mut initial_pose = None;
+
+        for stmt in parse_statements(src)? {
+            match stmt.name.as_str() {
+                "dimensions" => {
+                    let [x, y] = stmt.numbers()?;
+                    bounds = Some(Point::new(x, y));
+                }
+                "wall" => {
+                    let [start, end] = stmt.points()?;
+                    walls.push(Wall::new(start, end));
+                }
+                "initialRobotLoc" => {
+                    let (x, y, theta) = match stmt.args.len() {
+                        3 => {
+                            let [x, y, theta] = stmt.numbers()?;
+                            (x, y, theta)
+                        }
+                        _ => {
+                            let [x, y] = stmt.numbers()?;
+                            (x, y, 0.0)
+                        }
+                    };
+                    initial_pose = Some(Pose {
+                        pos: Point::new(x, y),
+                        theta,
+                    });
+                }
+                _ => return Err(stmt.error(ParseErrorKind::UnknownStatement(stmt.name.clone()))),
+            }
+        }
+
+        let end = ParseError {
+            line: src.lines().count() + 1,
+            column: 1,
+            kind: ParseErrorKind::Missing("dimensions"),
+        };
+        Ok(Self {
+            bounds: bounds.ok_or(end.clone())?,
+            walls,
+            initial_pose: initial_pose.ok_or(ParseError {
+                kind: ParseErrorKind::Missing("initialRobotLoc"),
+                ..end
+            })?,
+        })
+    }
+
+    /// The four walls soar draws around the arena.
+    pub fn boundary_walls(&self) -> [Wall; 4] {
+        let Point { x, y } = self.bounds;
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(x, 0.0),
+            Point::new(x, y),
+            Point::new(0.0, y),
+        ];
+        [0, 1, 2, 3].map(|i| Wall::new(corners[i], corners[(i + 1) % 4]))
+    }
+}
+
+impl FromStr for World {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownStatement(String),
+    Unexpected {
+        expected: &'static str,
+        found: String,
+    },
+    InvalidNumber(String),
+    Arity {
+        statement: String,
+        expected: String,
+        found: usize,
+    },
+    Missing(&'static str),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnknownStatement(name) => write!(f, "unknown statement `{name}`"),
+            ParseErrorKind::Unexpected { expected, found } => {
+                write!(f, "expected {expected}, found `{found}`")
+            }
+            ParseErrorKind::InvalidNumber(s) => write!(f, "invalid number `{s}`"),
+            ParseErrorKind::Arity {
+                statement,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{statement}` expects {expected}, got {found} argument(s)"
+            ),
+            ParseErrorKind::Missing(name) => write!(f, "missing `{name}` statement"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Arg {
+    Number(f64),
+    Point(Point),
+}
+
+/// One `name(arg, ...)` call of a soar-style configuration file.
+#[derive(Debug, Clone)]
+pub(crate) struct Statement {
+    pub(crate) name: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) args: Vec<Arg>,
+}
+
+impl Statement {
+    pub(crate) fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    fn arity<const N: usize>(&self, what: &str) -> ParseError {
+        self.error(ParseErrorKind::Arity {
+            statement: self.name.clone(),
+            expected: format!("{N} {what}"),
+            found: self.args.len(),
+        })
+    }
+
+    pub(crate) fn numbers<const N: usize>(&self) -> Result<[f64; N], ParseError> {
+        let mut out = [0.0; N];
+        if self.args.len() != N {
+            return Err(self.arity::<N>("numbers"));
+        }
+        for (o, arg) in out.iter_mut().zip(&self.args) {
+            match arg {
+                Arg::Number(n) => *o = *n,
+                Arg::Point(_) => return Err(self.arity::<N>("numbers")),
+            }
+        }
+        Ok(out)
+    }
+
+    pub(crate) fn points<const N: usize>(&self) -> Result<[Point; N], ParseError> {
+        let mut out = [Point::default(); N];
+        if self.args.len() != N {
+            return Err(self.arity::<N>("points"));
+        }
+        for (o, arg) in out.iter_mut().zip(&self.args) {
+            match arg {
+                Arg::Point(p) => *o = *p,
+                Arg::Number(_) => return Err(self.arity::<N>("points")),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Number(&'a str),
+    Open,
+    Close,
+    Comma,
+    End,
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(s) | Token::Number(s) => write!(f, "{s}"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::End => write!(f, "end of line"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    line: &'a str,
+    line_no: usize,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    /// Returns the next token with its 1-based column.
+    fn next(&mut self) -> (Token<'a>, usize) {
+        let rest = &self.line[self.pos..];
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        let column = self.pos + 1;
+        let Some(c) = trimmed.chars().next() else {
+            return (Token::End, column);
+        };
+        let len = match c {
+            '#' => {
+                self.pos = self.line.len();
+                return (Token::End, column);
+            }
+            '(' | ')' | ',' => 1,
+            c if c.is_alphabetic() || c == '_' => trimmed
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(trimmed.len()),
+            _ => trimmed
+                .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ',' | '#'))
+                .unwrap_or(trimmed.len()),
+        };
+        let text = &trimmed[..len];
+        self.pos += len;
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            c if c.is_alphabetic() || c == '_' => Token::Ident(text),
+            _ => Token::Number(text),
+        };
+        (token, column)
+    }
+
+    fn error(&self, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line_no,
+            column,
+            kind,
+        }
+    }
+
+    fn expect(&mut self, expected: Token<'static>, what: &'static str) -> Result<(), ParseError> {
+        match self.next() {
+            (token, _) if token == expected => Ok(()),
+            (found, column) => Err(self.unexpected(what, found, column)),
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str, found: Token, column: usize) -> ParseError {
+        self.error(
+            column,
+            ParseErrorKind::Unexpected {
+                expected,
+                found: found.to_string(),
+            },
+        )
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        match self.next() {
+            (Token::Number(text), column) => text
+                .parse()
+                .map_err(|_| self.error(column, ParseErrorKind::InvalidNumber(text.to_string()))),
+            (found, column) => Err(self.unexpected("a number", found, column)),
+        }
+    }
+
+    fn arg(&mut self) -> Result<Arg, ParseError> {
+        let save = self.pos;
+        if let (Token::Open, _) = self.next() {
+            let x = self.number()?;
+            self.expect(Token::Comma, "`,`")?;
+            let y = self.number()?;
+            self.expect(Token::Close, "`)`")?;
+            Ok(Arg::Point(Point::new(x, y)))
+        } else {
+            self.pos = save;
+            self.number().map(Arg::Number)
+        }
+    }
+}
+
+/// Splits a soar-style file into its `name(arg, ...)` calls, one per line.
+/// Blank lines and `#` comments are skipped.
+pub(crate) fn parse_statements(src: &str) -> Result<Vec<Statement>, ParseError> {
+    let mut statements = Vec::new();
+    for (idx, line) in src.lines().enumerate() {
+        let mut lexer = Lexer {
+            line,
+            line_no: idx + 1,
+            pos: 0,
+        };
+        let (name, column) = match lexer.next() {
+            (Token::End, _) => continue,
+            (Token::Ident(name), column) => (name, column),
+            (found, column) => return Err(lexer.unexpected("a statement", found, column)),
+        };
+        lexer.expect(Token::Open, "`(`")?;
+        let mut args = Vec::new();
+        let save = lexer.pos;
+        if !matches!(lexer.next(), (Token::Close, _)) {
+            lexer.pos = save;
+            loop {
+                args.push(lexer.arg()?);
+                match lexer.next() {
+                    (Token::Comma, _) => continue,
+                    (Token::Close, _) => break,
+                    (found, column) => return Err(lexer.unexpected("`,` or `)`", found, column)),
+                }
+            }
+        }
+        match lexer.next() {
+            (Token::End, _) => {}
+            (found, column) => return Err(lexer.unexpected("end of line", found, column)),
+        }
+        statements.push(Statement {
+            name: name.to_string(),
+            line: idx + 1,
+            column,
+            args,
+        });
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::Simulator, sonars::get_distance_right};
+
+    const TUTORIAL: &str = include_str!("../../worlds/tutorial.py");
+    const EMPTY: &str = include_str!("../../worlds/empty.py");
+
+    #[test]
+    fn test_empty_world() {
+        let world = World::parse(EMPTY).unwrap();
+        assert_eq!((world.bounds.x, world.bounds.y), (4.0, 4.0));
+        assert!(world.walls.is_empty());
+        assert_eq!(
+            (world.initial_pose.pos.x, world.initial_pose.pos.y),
+            (2.0, 2.0)
+        );
+        assert_eq!(world.initial_pose.theta, 0.0);
+    }
+
+    #[test]
+    fn test_tutorial_world() {
+        let world: World = TUTORIAL.parse().unwrap();
+        assert_eq!(world.walls.len(), 4);
+        let wall = world.walls[2];
+        assert_eq!((wall.start.x, wall.start.y), (2.0, 0.0));
+        assert_eq!((wall.end.x, wall.end.y), (2.0, 2.0));
+        assert_eq!(
+            (world.initial_pose.pos.x, world.initial_pose.pos.y),
+            (2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn test_comments_and_heading() {
+        let world = World::parse(
+            "# a comment\n\ndimensions(2.5, 3)  # trailing\ninitialRobotLoc(1, 1, -1.5e0)\n",
+        )
+        .unwrap();
+        assert_eq!((world.bounds.x, world.bounds.y), (2.5, 3.0));
+        assert_eq!(world.initial_pose.theta, -1.5);
+    }
+
+    #[test]
+    fn test_unknown_statement() {
+        let err = World::parse("dimensions(4,4)\n  door((0,1),(0,2))\n").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                line: 2,
+                column: 3,
+                kind: ParseErrorKind::UnknownStatement("door".to_string())
+            }
+        );
+        assert_eq!(err.to_string(), "2:3: unknown statement `door`");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let err = World::parse("wall((0,3) (1,3))").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 12));
+        let err = World::parse("dimensions(4,x)").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 14));
+        let err = World::parse("dimensions(4,4e)").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidNumber("4e".to_string()));
+        let err = World::parse("wall((0,3))").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Arity { found: 1, .. }));
+        let err = World::parse("initialRobotLoc(2, 2)").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Missing("dimensions"));
+    }
+
+    #[test]
+    fn test_simulate_tutorial() {
+        let world = World::parse(TUTORIAL).unwrap();
+        let sim = Simulator::from_world(&world);
+        // the robot starts facing +x with the wall from (2,2) to (3,2) 1m
+        // to its right
+        assert!((get_distance_right(&sim.sensor_input().sonars) - 1.0).abs() < 10e-9);
+    }
+}
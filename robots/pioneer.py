@@ -0,0 +1,12 @@
+# Pioneer 3-DX: eight front sonars, left to right
+radius(0.2)
+sonarMax(1.5)
+sonar((0.08, 0.134), 90)
+sonar((0.122, 0.118), 50)
+sonar((0.156, 0.077), 30)
+sonar((0.174, 0.0266), 10)
+sonar((0.174, -0.0266), -10)
+sonar((0.156, -0.077), -30)
+sonar((0.122, -0.118), -50)
+sonar((0.08, -0.134), -90)
+rightSonars(6, 7)